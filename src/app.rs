@@ -1,14 +1,38 @@
-use egui::{Pos2, Rect};
+use std::path::{Path, PathBuf};
 
 use crate::{
-    task_drawer::{draw_task, paint_rectangle, TaskPosition, RADII},
-    EstimateApp,
+    cached_layout,
+    command::{Command, History},
+    document,
+    file_format::{ExportFormat, ImportFormat},
+    scene::SUM_RADIUS,
+    simulate,
+    simulation_panel::draw_simulation,
+    task_drawer::{draw_layout, DrawOptions},
+    view::View,
+    EstimateApp, EstimateUnit, EstimateUnits, LayoutResult, LayoutStyle, RollupMode,
+    SimulationInputs, SimulationResult, Task, ThreePointEstimate, DEFAULT_ITERATIONS,
 };
 
+/// Storage key of the undo history, kept apart from `eframe::APP_KEY`
+/// so a history that no longer loads never costs us the document.
+const HISTORY_KEY: &str = "history";
+
+/// Storage key of the document, saved with `document::to_json` so it is versioned and migrated.
+const DOCUMENT_KEY: &str = "document";
+
+/// Stored estimate used to show what switching between time and story points does to a value.
+const EXAMPLE_ESTIMATE: f32 = 8.0;
+
+/// Storage key of a saved document that could not be loaded, kept until the user dismisses it.
+const RECOVERY_KEY: &str = "recovery";
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TemplateApp {
+    /// Persisted separately under `DOCUMENT_KEY`.
+    #[serde(skip)]
     estimate_app: EstimateApp,
 
     #[serde(skip)]
@@ -19,6 +43,91 @@ pub struct TemplateApp {
 
     #[serde(skip)]
     input_field_text: String,
+
+    /// Shown in the input window when `input_field_text` could not be parsed.
+    #[serde(skip)]
+    input_field_error: Option<String>,
+
+    /// Task picked up with X, moved under the selected task with V.
+    #[serde(skip)]
+    moving_task_id: Option<String>,
+
+    /// Last Monte Carlo run and the inputs it was run on. It is only run again once
+    /// the estimates or the shape of the tree change, not after every edit.
+    #[serde(skip)]
+    simulation: Option<(SimulationInputs, SimulationResult)>,
+
+    /// Persisted separately under `HISTORY_KEY`.
+    #[serde(skip)]
+    history: History,
+
+    /// The file the document was last opened from or saved to.
+    file_path: Option<PathBuf>,
+
+    /// Whether there are edits that have not been saved to `file_path`.
+    dirty: bool,
+
+    /// Asks before throwing away unsaved changes.
+    #[serde(skip)]
+    confirm_discard: Option<DiscardAction>,
+
+    /// Persisted separately under `RECOVERY_KEY`.
+    #[serde(skip)]
+    recovery: Vec<Recovery>,
+
+    /// What the last import could not interpret, shown until dismissed.
+    #[serde(skip)]
+    import_issues: Vec<String>,
+
+    /// Pan and zoom of the canvas.
+    #[serde(skip)]
+    view: View,
+
+    /// Applied by the central panel, which knows the size of the canvas.
+    #[serde(skip)]
+    view_command: Option<ViewCommand>,
+
+    /// Last layout of the document, cleared whenever the tasks change.
+    #[serde(skip)]
+    layout: Option<LayoutResult>,
+
+    /// "Max node width" while it is being dragged, applied when the drag stops.
+    #[serde(skip)]
+    max_node_width_draft: Option<f32>,
+
+    /// "h/day" while it is being dragged, applied when the drag stops.
+    #[serde(skip)]
+    hours_per_day_draft: Option<f32>,
+
+    /// Units waiting for the user to confirm a switch between time and story points.
+    #[serde(skip)]
+    confirm_units: Option<EstimateUnits>,
+}
+
+/// Saved state that could not be loaded, kept so the user can rescue it by hand.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Recovery {
+    error: String,
+    data: String,
+}
+
+/// App state saved before the document moved to `DOCUMENT_KEY`.
+#[derive(serde::Deserialize)]
+struct LegacyState {
+    estimate_app: EstimateApp,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum DiscardAction {
+    New,
+    Open,
+    Import(ImportFormat),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ViewCommand {
+    FitAll,
+    CenterOnSelection,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, PartialEq, Debug)]
@@ -26,6 +135,11 @@ enum InputFieldAction {
     Hide,
     CreateTask,
     EditEstimate,
+    RenameTask,
+    OpenFile,
+    SaveFileAs,
+    Import(ImportFormat),
+    Export(ExportFormat),
 }
 
 impl Default for TemplateApp {
@@ -35,6 +149,21 @@ impl Default for TemplateApp {
             input_field_state: InputFieldAction::Hide,
             selected_task_id: None,
             input_field_text: "".to_owned(),
+            input_field_error: None,
+            moving_task_id: None,
+            simulation: None,
+            history: History::default(),
+            file_path: None,
+            dirty: false,
+            confirm_discard: None,
+            recovery: Vec::new(),
+            import_issues: Vec::new(),
+            view: View::default(),
+            view_command: None,
+            layout: None,
+            max_node_width_draft: None,
+            hours_per_day_draft: None,
+            confirm_units: None,
         }
     }
 }
@@ -48,17 +177,304 @@ impl TemplateApp {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            let mut app: Self = eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+            app.load_document(storage);
+            return app;
         }
 
         Default::default()
     }
+
+    /// Loads the document from `storage`, migrating it if it was saved by an older version.
+    /// Anything that can't be read is kept in `recovery` instead of being thrown away.
+    fn load_document(&mut self, storage: &dyn eframe::Storage) {
+        self.recovery = eframe::get_value::<Vec<Recovery>>(storage, RECOVERY_KEY)
+            // Older versions kept a single recovery.
+            .or_else(|| {
+                eframe::get_value::<Option<Recovery>>(storage, RECOVERY_KEY)
+                    .flatten()
+                    .map(|recovery| vec![recovery])
+            })
+            .unwrap_or_default();
+
+        let loaded = match storage.get_string(DOCUMENT_KEY) {
+            Some(data) => document::from_json(&data)
+                .map(|loaded| Some((loaded.estimate_app, loaded.repaired_ids)))
+                .map_err(|error| (error.to_string(), data)),
+            // Older versions saved the document inside the app state.
+            None => match storage.get_string(eframe::APP_KEY) {
+                // It goes through the same migrations as an unversioned document file.
+                Some(data) => ron::from_str::<LegacyState>(&data)
+                    .map_err(|error| error.to_string())
+                    .and_then(|state| {
+                        serde_json::to_value(state.estimate_app).map_err(|error| error.to_string())
+                    })
+                    .and_then(|value| {
+                        document::from_value(value).map_err(|error| error.to_string())
+                    })
+                    .map(|loaded| Some((loaded.estimate_app, loaded.repaired_ids)))
+                    .map_err(|error| (error, data)),
+                None => Ok(None),
+            },
+        };
+
+        match loaded {
+            Ok(Some((estimate_app, repaired_ids))) => {
+                self.estimate_app = estimate_app;
+                // Commands in the history refer to tasks by ID, after a repair they may hit the wrong task.
+                if repaired_ids == 0 {
+                    self.history = eframe::get_value(storage, HISTORY_KEY).unwrap_or_default();
+                } else {
+                    self.history = History::default();
+                }
+            }
+            Ok(None) => {}
+            Err((error, data)) => {
+                log::error!("Could not load the saved document: {}", error);
+                // Kept next to older recoveries that haven't been looked at yet.
+                self.recovery.push(Recovery { error, data });
+            }
+        }
+    }
+
+    /// Applies what was typed into the input window, from Enter or the Submit button.
+    /// The window stays open with an error if the input can't be used.
+    fn submit_input(&mut self) {
+        match self.input_field_state {
+            InputFieldAction::Hide => {}
+            InputFieldAction::CreateTask => {
+                self.input_field_state = InputFieldAction::Hide;
+
+                let task_id = self.estimate_app.generate_task_id();
+                let command = Command::Insert {
+                    parent_id: self.selected_task_id.clone(),
+                    index: None,
+                    task: Task::new(&task_id, &self.input_field_text, 0.0),
+                };
+                if self.execute(command) {
+                    self.selected_task_id = Some(task_id);
+                }
+            }
+            InputFieldAction::EditEstimate => {
+                // "o/m/p" sets a three-point range, anything else a single value.
+                match ThreePointEstimate::parse(&self.input_field_text, &self.estimate_app.units) {
+                    Ok(range) => {
+                        if let Some(id) = self.selected_task_id.clone() {
+                            // An unchanged estimate doesn't need an undo entry.
+                            let unchanged = self
+                                .estimate_app
+                                .find_task(&id)
+                                .is_some_and(|task| task.three_point_estimate() == range);
+                            if !unchanged {
+                                self.execute(Command::SetEstimate {
+                                    id,
+                                    estimate: range,
+                                });
+                            }
+                        }
+                        self.input_field_state = InputFieldAction::Hide;
+                    }
+                    // Keep the window open so the input can be corrected.
+                    Err(error) => self.input_field_error = Some(error.to_string()),
+                }
+            }
+            InputFieldAction::RenameTask => {
+                let name = self.input_field_text.trim().to_owned();
+                if name.is_empty() {
+                    self.input_field_error = Some("A task needs a name".to_owned());
+                } else {
+                    if let Some(id) = self.selected_task_id.clone() {
+                        self.execute(Command::Rename { id, name });
+                    }
+                    self.input_field_state = InputFieldAction::Hide;
+                }
+            }
+            InputFieldAction::OpenFile => {
+                let path = PathBuf::from(self.input_field_text.trim());
+                match self.open_document(&path) {
+                    Ok(()) => self.input_field_state = InputFieldAction::Hide,
+                    Err(error) => self.input_field_error = Some(error.to_string()),
+                }
+            }
+            InputFieldAction::SaveFileAs => {
+                let mut path = PathBuf::from(self.input_field_text.trim());
+                if path.extension().is_none() {
+                    path.set_extension(document::FILE_EXTENSION);
+                }
+                match self.save_document(&path) {
+                    Ok(()) => self.input_field_state = InputFieldAction::Hide,
+                    Err(error) => self.input_field_error = Some(error.to_string()),
+                }
+            }
+            InputFieldAction::Import(format) => {
+                let path = PathBuf::from(self.input_field_text.trim());
+                match self.import_document(&path, format) {
+                    Ok(()) => self.input_field_state = InputFieldAction::Hide,
+                    Err(error) => self.input_field_error = Some(error),
+                }
+            }
+            InputFieldAction::Export(format) => {
+                let mut path = PathBuf::from(self.input_field_text.trim());
+                if path.extension().is_none() {
+                    path.set_extension(format.extension());
+                }
+                match self.export_document(&path, format) {
+                    Ok(()) => self.input_field_state = InputFieldAction::Hide,
+                    Err(error) => self.input_field_error = Some(error),
+                }
+            }
+        }
+    }
+
+    /// Applies `command` through the undo history. Every edit of the tasks goes through here.
+    fn execute(&mut self, command: Command) -> bool {
+        let changed = self.history.execute(&mut self.estimate_app, command);
+        if changed {
+            self.layout = None;
+            self.dirty = true;
+        }
+        changed
+    }
+
+    fn undo(&mut self) {
+        if self.history.undo(&mut self.estimate_app) {
+            self.after_history_change();
+        }
+    }
+
+    fn redo(&mut self) {
+        if self.history.redo(&mut self.estimate_app) {
+            self.after_history_change();
+        }
+    }
+
+    fn after_history_change(&mut self) {
+        self.layout = None;
+        self.dirty = true;
+        // The selected task may have been removed by the undo or redo.
+        if let Some(id) = &self.selected_task_id {
+            if self.estimate_app.find_task(id).is_none() {
+                self.selected_task_id = None;
+            }
+        }
+    }
+
+    /// Replaces the current document, e.g. after File → New or Open.
+    fn replace_document(&mut self, estimate_app: EstimateApp, file_path: Option<PathBuf>) {
+        self.estimate_app = estimate_app;
+        self.file_path = file_path;
+        self.dirty = false;
+        self.history.clear();
+        self.selected_task_id = None;
+        self.moving_task_id = None;
+        self.simulation = None;
+        self.layout = None;
+    }
+
+    fn open_document(&mut self, path: &Path) -> Result<(), document::DocumentError> {
+        let estimate_app = document::load(path)?;
+        self.replace_document(estimate_app, Some(path.to_owned()));
+        Ok(())
+    }
+
+    fn save_document(&mut self, path: &Path) -> Result<(), document::DocumentError> {
+        document::save(path, &self.estimate_app)?;
+        self.file_path = Some(path.to_owned());
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Saves to the current file, or asks for a path if the document has never been saved.
+    fn save_to_file(&mut self) {
+        match self.file_path.clone() {
+            Some(path) => {
+                if let Err(error) = self.save_document(&path) {
+                    self.show_path_input(InputFieldAction::SaveFileAs);
+                    self.input_field_error = Some(error.to_string());
+                }
+            }
+            None => self.show_path_input(InputFieldAction::SaveFileAs),
+        }
+    }
+
+    /// Opens the input window to type a file path for `action`.
+    fn show_path_input(&mut self, action: InputFieldAction) {
+        self.input_field_text = self
+            .file_path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.input_field_error = None;
+        self.input_field_state = action;
+    }
+
+    fn discard_and(&mut self, action: DiscardAction) {
+        match action {
+            DiscardAction::New => self.replace_document(EstimateApp::new(), None),
+            DiscardAction::Open => self.show_path_input(InputFieldAction::OpenFile),
+            DiscardAction::Import(format) => self.show_path_input(InputFieldAction::Import(format)),
+        }
+    }
+
+    /// Replaces the document with one read from `path`. It is not tied to a file until saved.
+    fn import_document(&mut self, path: &Path, format: ImportFormat) -> Result<(), String> {
+        let text = std::fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let (estimate_app, issues) = format.import(&text, self.estimate_app.units)?;
+        self.replace_document(estimate_app, None);
+        self.dirty = true;
+        self.import_issues = issues;
+        Ok(())
+    }
+
+    fn export_document(&self, path: &Path, format: ExportFormat) -> Result<(), String> {
+        let data = format.export(&self.estimate_app)?;
+        std::fs::write(path, data).map_err(|error| error.to_string())
+    }
+
+    /// Runs `action` right away, or asks first if there are unsaved changes.
+    fn confirm_discard_and(&mut self, action: DiscardAction) {
+        if self.dirty {
+            self.confirm_discard = Some(action);
+        } else {
+            self.discard_and(action);
+        }
+    }
+
+    /// File name and unsaved marker shown in the top bar.
+    fn document_title(&self) -> String {
+        let name = self
+            .file_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Untitled".to_owned());
+        if self.dirty {
+            format!("{} •", name)
+        } else {
+            name
+        }
+    }
+
+    /// Opens the input window with the name of the task with `id`, if it exists.
+    fn start_rename(&mut self, id: &str) {
+        if let Some(task) = self.estimate_app.find_task(id) {
+            self.input_field_text = task.name.clone();
+            self.input_field_error = None;
+            self.input_field_state = InputFieldAction::RenameTask;
+        }
+    }
 }
 
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, eframe::APP_KEY, self);
+        match document::to_json(&self.estimate_app) {
+            Ok(data) => storage.set_string(DOCUMENT_KEY, data),
+            Err(error) => log::error!("Could not save the document: {}", error),
+        }
+        eframe::set_value(storage, HISTORY_KEY, &self.history);
+        eframe::set_value(storage, RECOVERY_KEY, &self.recovery);
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
@@ -69,11 +485,13 @@ impl eframe::App for TemplateApp {
         {
             self.input_field_state = InputFieldAction::CreateTask;
             self.input_field_text = "".to_owned();
+            self.input_field_error = None;
         }
         if ctx.input(|i| i.key_pressed(egui::Key::Escape))
             && self.input_field_state == InputFieldAction::Hide
         {
             self.selected_task_id = None;
+            self.moving_task_id = None;
         }
         if ctx.input(|i| i.key_pressed(egui::Key::Escape))
             && self.input_field_state != InputFieldAction::Hide
@@ -97,47 +515,98 @@ impl eframe::App for TemplateApp {
             self.selected_task_id = id;
         }
 
+        if ctx.input(|i| i.modifiers.is_none() && i.key_pressed(egui::Key::F))
+            && self.input_field_state == InputFieldAction::Hide
+        {
+            self.view_command = Some(ViewCommand::FitAll);
+        }
+        if ctx.input(|i| i.modifiers.is_none() && i.key_pressed(egui::Key::C))
+            && self.input_field_state == InputFieldAction::Hide
+        {
+            self.view_command = Some(ViewCommand::CenterOnSelection);
+        }
+
+        if ctx.input(|i| i.modifiers.is_none() && i.key_pressed(egui::Key::R))
+            && self.input_field_state == InputFieldAction::Hide
+        {
+            if let Some(id) = self.selected_task_id.clone() {
+                self.start_rename(&id);
+            }
+        }
+
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::Z))
+            && self.input_field_state == InputFieldAction::Hide
+        {
+            if ctx.input(|i| i.modifiers.shift) {
+                self.redo();
+            } else {
+                self.undo();
+            }
+        }
+
+        if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::S))
+            && self.input_field_state == InputFieldAction::Hide
+            && !cfg!(target_arch = "wasm32")
+        {
+            self.save_to_file();
+        }
+
+        // Restructuring the tree.
+        if self.input_field_state == InputFieldAction::Hide {
+            if let Some(id) = self.selected_task_id.clone() {
+                if ctx.input(|i| {
+                    i.key_pressed(egui::Key::Delete) || i.key_pressed(egui::Key::Backspace)
+                }) {
+                    let parent_id = self.estimate_app.parent_id(&id);
+                    if self.execute(Command::Remove { id }) {
+                        self.selected_task_id = parent_id;
+                    }
+                } else if ctx.input(|i| i.modifiers.alt && i.key_pressed(egui::Key::ArrowUp)) {
+                    self.execute(Command::MoveUp { id });
+                } else if ctx.input(|i| i.modifiers.alt && i.key_pressed(egui::Key::ArrowDown)) {
+                    self.execute(Command::MoveDown { id });
+                } else if ctx.input(|i| i.modifiers.shift && i.key_pressed(egui::Key::Tab)) {
+                    self.execute(Command::Outdent { id });
+                } else if ctx.input(|i| !i.modifiers.shift && i.key_pressed(egui::Key::Tab)) {
+                    self.execute(Command::Indent { id });
+                }
+            }
+            if ctx.input(|i| i.modifiers.is_none() && i.key_pressed(egui::Key::X)) {
+                self.moving_task_id = self.selected_task_id.clone();
+            }
+            if ctx.input(|i| i.modifiers.is_none() && i.key_pressed(egui::Key::V)) {
+                // Drop the picked up task under the selection, or at the top level if nothing is selected.
+                if let Some(id) = self.moving_task_id.take() {
+                    let command = Command::Move {
+                        id: id.clone(),
+                        parent_id: self.selected_task_id.clone(),
+                        index: None,
+                    };
+                    if self.execute(command) {
+                        self.selected_task_id = Some(id);
+                    }
+                }
+            }
+        }
+
         if ctx.input(|i| i.key_pressed(egui::Key::Enter)) {
-            println!("Enter pressed, current state: {:?}", self.input_field_state);
             match self.input_field_state {
                 InputFieldAction::Hide => {
-                    println!("Enter pressed, showing edit task estimate");
                     if let Some(id) = &self.selected_task_id {
                         let current_task = self.estimate_app.find_task(id);
                         if let Some(task) = current_task {
-                            println!("Task found: {:?}", task);
-                            self.input_field_text = task.estimate.to_string();
+                            // Unrounded, so submitting the text unchanged keeps the estimate.
+                            let units = &self.estimate_app.units;
+                            self.input_field_text = match task.three_point {
+                                Some(range) => range.format_exact(units),
+                                None => units.format_exact(task.estimate),
+                            };
+                            self.input_field_error = None;
                             self.input_field_state = InputFieldAction::EditEstimate;
                         }
                     }
                 }
-                InputFieldAction::CreateTask => {
-                    println!("Enter pressed, creating task and hiding input field");
-                    self.input_field_text = self.input_field_text.clone();
-                    self.input_field_state = InputFieldAction::Hide;
-
-                    if let Some(id) = &self.selected_task_id {
-                        let task = self.estimate_app.find_mut_task(id.as_str());
-                        if let Some(task) = task {
-                            let task_id = task.add_child_task(&self.input_field_text, 0);
-                            self.selected_task_id = Some(task_id);
-                        }
-                    } else {
-                        let task_id = self.estimate_app.add_task(&self.input_field_text);
-                        self.selected_task_id = Some(task_id);
-                    }
-                }
-                InputFieldAction::EditEstimate => {
-                    println!("Enter pressed, saving estimate and hiding input field");
-                    if let Some(id) = &self.selected_task_id {
-                        let current_task = self.estimate_app.find_mut_task(id);
-                        if let Some(task) = current_task {
-                            task.estimate = self.input_field_text.parse().unwrap_or(0);
-                            println!("Task saved: {:?}", task);
-                        }
-                    }
-                    self.input_field_state = InputFieldAction::Hide;
-                }
+                _ => self.submit_input(),
             }
         }
 
@@ -148,73 +617,432 @@ impl eframe::App for TemplateApp {
                 // NOTE: no File->Quit on web pages!
                 let is_web = cfg!(target_arch = "wasm32");
                 if !is_web {
-                    ui.menu_button(format!("File-{:?}", self.selected_task_id), |ui| {
+                    ui.menu_button("File", |ui| {
+                        if ui.button("New").clicked() {
+                            self.confirm_discard_and(DiscardAction::New);
+                            ui.close_menu();
+                        }
+                        if ui.button("Open…").clicked() {
+                            self.confirm_discard_and(DiscardAction::Open);
+                            ui.close_menu();
+                        }
+                        if ui.button("Save").clicked() {
+                            self.save_to_file();
+                            ui.close_menu();
+                        }
+                        if ui.button("Save As…").clicked() {
+                            self.show_path_input(InputFieldAction::SaveFileAs);
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        ui.menu_button("Import", |ui| {
+                            for format in ImportFormat::ALL {
+                                if ui.button(format!("{}…", format.label())).clicked() {
+                                    self.confirm_discard_and(DiscardAction::Import(format));
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        ui.menu_button("Export", |ui| {
+                            for format in ExportFormat::ALL {
+                                if ui.button(format!("{}…", format.label())).clicked() {
+                                    self.show_path_input(InputFieldAction::Export(format));
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        ui.separator();
                         if ui.button("Quit").clicked() {
                             ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                         }
                     });
                     ui.add_space(16.0);
-                    let _ = ui.button(format!("{:?}", self.input_field_state));
+                    ui.label(self.document_title());
                 }
 
+                ui.menu_button("Edit", |ui| {
+                    if ui
+                        .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                        .clicked()
+                    {
+                        self.undo();
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                        .clicked()
+                    {
+                        self.redo();
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("View", |ui| {
+                    if ui.button("Fit all").clicked() {
+                        self.view_command = Some(ViewCommand::FitAll);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.selected_task_id.is_some(),
+                            egui::Button::new("Center on selection"),
+                        )
+                        .clicked()
+                    {
+                        self.view_command = Some(ViewCommand::CenterOnSelection);
+                        ui.close_menu();
+                    }
+                    if ui.button("Reset zoom").clicked() {
+                        self.view = View::default();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    let mut layout_style = self.estimate_app.layout_style;
+                    egui::ComboBox::from_label("Layout")
+                        .selected_text(layout_style.to_string())
+                        .show_ui(ui, |ui| {
+                            for style in LayoutStyle::ALL {
+                                ui.selectable_value(&mut layout_style, style, style.to_string());
+                            }
+                        });
+                    if layout_style != self.estimate_app.layout_style {
+                        self.execute(Command::SetLayoutStyle {
+                            style: layout_style,
+                        });
+                        self.view_command = Some(ViewCommand::FitAll);
+                    }
+                    if let Some(width) = setting_drag_value(
+                        ui,
+                        &mut self.max_node_width_draft,
+                        self.estimate_app.max_node_width,
+                        |drag_value| {
+                            drag_value
+                                .range(100.0..=600.0)
+                                .prefix("Max node width ")
+                                .suffix(" pt")
+                        },
+                    ) {
+                        self.execute(Command::SetMaxNodeWidth { width });
+                    }
+                });
+
+                ui.menu_button("Settings", |ui| {
+                    // Settings are edited on copies and applied as commands, so they can be undone.
+                    let mut units = self.estimate_app.units;
+                    egui::ComboBox::from_label("Unit")
+                        .selected_text(units.unit.to_string())
+                        .show_ui(ui, |ui| {
+                            for unit in EstimateUnit::ALL {
+                                ui.selectable_value(&mut units.unit, unit, unit.to_string());
+                            }
+                        });
+                    if let Some(hours_per_day) = setting_drag_value(
+                        ui,
+                        &mut self.hours_per_day_draft,
+                        units.hours_per_day,
+                        |drag_value| drag_value.range(1.0..=24.0).suffix(" h/day"),
+                    ) {
+                        units.hours_per_day = hours_per_day;
+                    }
+                    if units.unit.is_time() != self.estimate_app.units.unit.is_time() {
+                        // Estimates can't be converted between time and story points.
+                        self.confirm_units = Some(units);
+                        ui.close_menu();
+                    } else if units != self.estimate_app.units {
+                        self.execute(Command::SetUnits { units });
+                    }
+                    ui.separator();
+                    let mut rollup_mode = self.estimate_app.rollup_mode;
+                    egui::ComboBox::from_label("Rollup")
+                        .selected_text(rollup_mode.to_string())
+                        .show_ui(ui, |ui| {
+                            for mode in RollupMode::ALL {
+                                ui.selectable_value(&mut rollup_mode, mode, mode.to_string());
+                            }
+                        });
+                    if rollup_mode != self.estimate_app.rollup_mode {
+                        self.execute(Command::SetRollupMode { mode: rollup_mode });
+                    }
+                    let selected_task = self
+                        .selected_task_id
+                        .as_deref()
+                        .and_then(|id| self.estimate_app.find_task(id));
+                    if let Some(task) = selected_task {
+                        let id = task.id.clone();
+                        let mut task_mode = task.rollup_mode;
+                        egui::ComboBox::from_label("Selected task rollup")
+                            .selected_text(match task_mode {
+                                Some(mode) => mode.to_string(),
+                                None => "Document default".to_owned(),
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut task_mode, None, "Document default");
+                                for mode in RollupMode::ALL {
+                                    ui.selectable_value(
+                                        &mut task_mode,
+                                        Some(mode),
+                                        mode.to_string(),
+                                    );
+                                }
+                            });
+                        if task_mode != task.rollup_mode {
+                            self.execute(Command::SetTaskRollupMode {
+                                id,
+                                mode: task_mode,
+                            });
+                        }
+                    }
+                });
+
                 egui::widgets::global_theme_preference_buttons(ui);
+                if let Some(task) = self
+                    .moving_task_id
+                    .as_deref()
+                    .and_then(|id| self.estimate_app.find_task(id))
+                {
+                    ui.add_space(16.0);
+                    ui.label(format!("Moving \"{}\", press V to drop", task.name));
+                }
+                ui.add_space(16.0);
+                ui.label(format!(
+                    "Total: {}",
+                    self.estimate_app
+                        .pert_total()
+                        .format(&self.estimate_app.units)
+                ));
             });
         });
 
+        egui::SidePanel::right("simulation_panel").show(ctx, |ui| {
+            ui.heading("Simulation");
+            if ui.button("Re-run").clicked() {
+                self.simulation = None;
+            }
+            let inputs = SimulationInputs::of(&self.estimate_app);
+            if self
+                .simulation
+                .as_ref()
+                .is_some_and(|(simulated, _)| *simulated != inputs)
+            {
+                self.simulation = None;
+            }
+            let (_, simulation) = self.simulation.get_or_insert_with(|| {
+                // A fresh seed on every run, so "Re-run" actually shows the spread.
+                let seed = ctx.input(|i| i.time.to_bits());
+                let result = simulate(&self.estimate_app, DEFAULT_ITERATIONS, seed);
+                (inputs, result)
+            });
+            draw_simulation(ui, simulation, &self.estimate_app.units);
+        });
+
         egui::CentralPanel::default().show(ctx, |ui| {
             let (response, painter) = ui.allocate_painter(
                 egui::Vec2::new(ui.available_width(), ui.available_height()),
-                egui::Sense::click(),
+                egui::Sense::click_and_drag(),
             );
+            let canvas = response.rect;
+
+            // The layout is only recomputed after an edit or when the canvas is resized,
+            // panning and zooming just change `to_screen`.
+            let layout = cached_layout(self.layout.take(), &self.estimate_app, canvas.size());
 
+            let hit_task_id = response
+                .interact_pointer_pos()
+                .and_then(|pointer| layout.node_at(self.view.to_screen(canvas).inverse() * pointer))
+                .map(|node| node.task_id.clone());
             if response.clicked() {
-                self.selected_task_id = None;
+                self.selected_task_id = hit_task_id.clone();
             }
-
-            let rect = Rect::from_center_size(response.rect.center(), RADII * 2.0);
-            paint_rectangle(&painter, rect, false, "Root".to_string(), None, 0);
-
-            let mut placed_positions: Vec<Pos2> = Vec::new();
-            // Ensure that you get mutable access to tasks (assuming get_tasks_mut() exists).
-            let tasks = self.estimate_app.get_tasks_mut();
-            let num_tasks = tasks.len();
-            if num_tasks > 0 {
-                for (index, task) in tasks.iter().enumerate() {
-                    //
-                    let draw_task_response = draw_task(
-                        &painter,
-                        ui,
-                        task,
-                        response.rect.width().min(response.rect.height()) * 0.3,
-                        &placed_positions,
-                        self.selected_task_id.as_deref(),
-                        TaskPosition::new(
-                            task.id.clone(),
-                            response.rect.center(),
-                            rect,
-                            false,
-                            index,
-                            num_tasks,
-                            0,
-                        ),
-                    );
-                    placed_positions.push(draw_task_response.position);
-                    if let Some(task_id) = draw_task_response.clicked_task_id {
-                        self.selected_task_id = Some(task_id);
+            if response.double_clicked() && self.input_field_state == InputFieldAction::Hide {
+                if let Some(task_id) = hit_task_id {
+                    self.start_rename(&task_id);
+                    self.selected_task_id = Some(task_id);
+                }
+            }
+            if response.dragged() {
+                self.view.pan(response.drag_delta());
+            }
+            if response.hovered() {
+                // Pinch and ctrl+scroll arrive as zoom_delta, plain scrolling zooms as well.
+                let (zoom_delta, scroll, pointer) = ui.input(|i| {
+                    (
+                        i.zoom_delta(),
+                        i.smooth_scroll_delta.y,
+                        i.pointer.hover_pos(),
+                    )
+                });
+                let factor = zoom_delta * (scroll / 200.0).exp();
+                if let Some(pointer) = pointer.filter(|_| factor != 1.0) {
+                    self.view.zoom_by(factor, pointer - canvas.center());
+                }
+            }
+            if let Some(command) = self.view_command.take() {
+                match command {
+                    ViewCommand::FitAll => {
+                        self.view
+                            .fit(layout.bounds.expand(SUM_RADIUS), canvas.size());
+                    }
+                    ViewCommand::CenterOnSelection => {
+                        let selected = self
+                            .selected_task_id
+                            .as_deref()
+                            .and_then(|id| layout.node(id));
+                        if let Some(node) = selected {
+                            self.view.center_on(node.rect.center());
+                        }
                     }
                 }
             }
+
+            let options = DrawOptions {
+                selected_task_id: self.selected_task_id.as_deref(),
+                units: &self.estimate_app.units,
+                to_screen: self.view.to_screen(canvas),
+            };
+            draw_layout(&painter, &layout, &options);
+            self.layout = Some(layout);
         });
 
         // Optionally, if you want to draw the input field when show_input_field is true:
         if self.input_field_state != InputFieldAction::Hide {
-            egui::Window::new("New Task").show(ctx, |ui| {
-                ui.text_edit_singleline(&mut self.input_field_text)
-                    .request_focus();
-                if ui.button("Submit").clicked() {
-                    self.input_field_state = InputFieldAction::Hide;
-                }
-            });
+            let title = match self.input_field_state {
+                InputFieldAction::EditEstimate => "Edit Estimate".to_owned(),
+                InputFieldAction::RenameTask => "Rename Task".to_owned(),
+                InputFieldAction::OpenFile => "Open File".to_owned(),
+                InputFieldAction::SaveFileAs => "Save As".to_owned(),
+                InputFieldAction::Import(format) => format!("Import {}", format.label()),
+                InputFieldAction::Export(format) => format!("Export {}", format.label()),
+                _ => "New Task".to_owned(),
+            };
+            egui::Window::new(title)
+                .id(egui::Id::new("input_field_window"))
+                .show(ctx, |ui| {
+                    ui.text_edit_singleline(&mut self.input_field_text)
+                        .request_focus();
+                    if let Some(error) = &self.input_field_error {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+                    if ui.button("Submit").clicked() {
+                        self.submit_input();
+                    }
+                });
+        }
+
+        if !self.recovery.is_empty() {
+            let mut dismissed = None;
+            egui::Window::new("Saved mindmap could not be loaded")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(
+                        "The saved data has been kept. Copy it to rescue your work, \
+                         it stays here until you delete it.",
+                    );
+                    for (index, recovery) in self.recovery.iter().enumerate() {
+                        ui.separator();
+                        ui.label(&recovery.error);
+                        ui.horizontal(|ui| {
+                            if ui.button("Copy saved data").clicked() {
+                                ctx.copy_text(recovery.data.clone());
+                            }
+                            if ui.button("Delete saved data").clicked() {
+                                dismissed = Some(index);
+                            }
+                        });
+                    }
+                });
+            if let Some(index) = dismissed {
+                self.recovery.remove(index);
+            }
+        }
+
+        if !self.import_issues.is_empty() {
+            let mut dismiss = false;
+            egui::Window::new("Import notes")
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{} parts of the file could not be fully imported:",
+                        self.import_issues.len()
+                    ));
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            for issue in &self.import_issues {
+                                ui.label(issue);
+                            }
+                        });
+                    if ui.button("Close").clicked() {
+                        dismiss = true;
+                    }
+                });
+            if dismiss {
+                self.import_issues.clear();
+            }
+        }
+
+        if let Some(units) = self.confirm_units {
+            let current = self.estimate_app.units;
+            egui::Window::new("Change unit")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(format!(
+                        "{} and {} can't be converted into each other, so every estimate \
+                         keeps its number and only its unit changes: {} becomes {}.",
+                        current.unit,
+                        units.unit.to_string().to_lowercase(),
+                        current.format(EXAMPLE_ESTIMATE),
+                        units.format(EXAMPLE_ESTIMATE),
+                    ));
+                    ui.horizontal(|ui| {
+                        if ui.button(format!("Switch to {}", units.unit)).clicked() {
+                            self.confirm_units = None;
+                            self.execute(Command::SetUnits { units });
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_units = None;
+                        }
+                    });
+                });
+        }
+
+        if let Some(action) = self.confirm_discard {
+            egui::Window::new("Unsaved changes")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("The current document has unsaved changes.");
+                    ui.horizontal(|ui| {
+                        if ui.button("Discard changes").clicked() {
+                            self.confirm_discard = None;
+                            self.discard_and(action);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_discard = None;
+                        }
+                    });
+                });
         }
     }
 }
+
+/// Shows a `DragValue` for a setting whose value is `current`, set up by `customize`.
+/// Dragging only changes `draft`, and typed text is only taken when the field loses focus,
+/// so the new value is returned once per edit and becomes a single undoable command.
+fn setting_drag_value(
+    ui: &mut egui::Ui,
+    draft: &mut Option<f32>,
+    current: f32,
+    customize: impl FnOnce(egui::DragValue<'_>) -> egui::DragValue<'_>,
+) -> Option<f32> {
+    let mut value = draft.unwrap_or(current);
+    let response = ui.add(customize(
+        egui::DragValue::new(&mut value).update_while_editing(false),
+    ));
+    if response.dragged() {
+        *draft = Some(value);
+        return None;
+    }
+    let value = draft.take().unwrap_or(value);
+    (value != current).then_some(value)
+}
@@ -0,0 +1,389 @@
+//! Works on saved mindmap documents without opening a window, for scripts and CI pipelines.
+//! Run without arguments for usage.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitCode;
+
+use eframe_template::{document, png, EstimateApp, ExportFormat, Rollup, Task, ThreePointEstimate};
+
+const USAGE: &str = "\
+Usage: estimate <command> <document> [options]
+
+Commands:
+  total <document>                       Print the rolled-up and PERT totals
+  tree <document>                        Print the task tree with IDs, estimates and rollups
+  export <document> --format <format> [--output <file>] [--scale <scale>]
+                                         Export to md, opml, csv, svg, png, dot, mmd or html,
+                                         to standard output unless --output is given
+  add <document> <name> [--parent <id>] [--estimate <estimate>]
+                                         Add a task and print its ID
+  set-estimate <document> <id> <estimate>
+                                         Set a single estimate such as 8h or a range such as 2h/4h/8h
+  validate <document>                    Report problems, exit with status 1 if there are any
+";
+
+/// A failed command, with the exit status to report.
+enum Error {
+    /// The arguments don't make sense, the usage is printed after the message.
+    Usage(String),
+    Failed(String),
+}
+
+fn main() -> ExitCode {
+    #[cfg(not(target_arch = "wasm32"))]
+    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(code) => code,
+        Err(Error::Usage(message)) => {
+            eprintln!("{message}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(Error::Failed(message)) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<ExitCode, Error> {
+    let Some((command, args)) = args.split_first() else {
+        return Err(Error::Usage("Missing command".to_owned()));
+    };
+    if command == "help" || command == "--help" || command == "-h" {
+        print!("{USAGE}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let Some((path, args)) = args.split_first() else {
+        return Err(Error::Usage("Missing document".to_owned()));
+    };
+    let path = Path::new(path);
+    let mut app = document::load(path)
+        .map_err(|error| Error::Failed(format!("Could not read {}: {}", path.display(), error)))?;
+    let options = Options::parse(args)?;
+
+    match command.as_str() {
+        "total" => {
+            options.expect(&[], 0)?;
+            println!("Total: {}", app.units.format(app.total()));
+            println!("PERT:  {}", app.pert_total().format(&app.units));
+        }
+        "tree" => {
+            options.expect(&[], 0)?;
+            print!("{}", tree(&app));
+        }
+        "export" => {
+            options.expect(&["--format", "--output", "--scale"], 0)?;
+            let name = options
+                .value("--format")
+                .ok_or_else(|| Error::Usage("Missing --format".to_owned()))?;
+            let format = ExportFormat::from_extension(name)
+                .ok_or_else(|| Error::Usage(format!("Unknown format {name}")))?;
+            let data = match (format, options.value("--scale")) {
+                (ExportFormat::Png, Some(scale)) => {
+                    let scale = scale
+                        .parse()
+                        .map_err(|_| Error::Usage(format!("Not a valid scale: {scale}")))?;
+                    png::to_png(&app, scale).map_err(|error| error.to_string())
+                }
+                (_, Some(_)) => return Err(Error::Usage("--scale only applies to png".to_owned())),
+                (format, None) => format.export(&app),
+            }
+            .map_err(Error::Failed)?;
+            match options.value("--output") {
+                Some(output) => std::fs::write(output, data)
+                    .map_err(|error| Error::Failed(format!("Could not write {output}: {error}")))?,
+                None if format == ExportFormat::Png => {
+                    return Err(Error::Usage("png needs --output".to_owned()))
+                }
+                None => print!("{}", String::from_utf8_lossy(&data)),
+            }
+        }
+        "add" => {
+            options.expect(&["--parent", "--estimate"], 1)?;
+            let name = options.positional[0];
+            let id = match options.value("--parent") {
+                Some(parent) => app
+                    .add_child_task(parent, name, 0.0)
+                    .ok_or_else(|| Error::Failed(format!("No task with ID {parent}")))?,
+                None => app.add_task(name),
+            };
+            if let Some(estimate) = options.value("--estimate") {
+                set_estimate(&mut app, &id, estimate)?;
+            }
+            save(path, &app)?;
+            println!("{id}");
+        }
+        "set-estimate" => {
+            options.expect(&[], 2)?;
+            set_estimate(&mut app, options.positional[0], options.positional[1])?;
+            save(path, &app)?;
+        }
+        "validate" => {
+            options.expect(&[], 0)?;
+            let issues = eframe_template::validate(&app);
+            if issues.is_empty() {
+                println!("No problems found");
+                return Ok(ExitCode::SUCCESS);
+            }
+            for issue in &issues {
+                println!("{issue}");
+            }
+            return Ok(ExitCode::FAILURE);
+        }
+        _ => return Err(Error::Usage(format!("Unknown command {command}"))),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The arguments after the document: `--name value` pairs and everything else in order.
+struct Options<'a> {
+    named: Vec<(&'a str, &'a str)>,
+    positional: Vec<&'a str>,
+}
+
+impl<'a> Options<'a> {
+    fn parse(args: &'a [String]) -> Result<Self, Error> {
+        let mut options = Options {
+            named: Vec::new(),
+            positional: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") {
+                let value = args
+                    .next()
+                    .ok_or_else(|| Error::Usage(format!("Missing value for {arg}")))?;
+                options.named.push((arg, value));
+            } else {
+                options.positional.push(arg);
+            }
+        }
+        Ok(options)
+    }
+
+    /// Fails unless only the `allowed` options and exactly `positional` other arguments were given.
+    fn expect(&self, allowed: &[&str], positional: usize) -> Result<(), Error> {
+        if let Some((name, _)) = self.named.iter().find(|(name, _)| !allowed.contains(name)) {
+            return Err(Error::Usage(format!("Unknown option {name}")));
+        }
+        if self.positional.len() != positional {
+            return Err(Error::Usage(format!(
+                "Expected {} arguments after the document, got {}",
+                positional,
+                self.positional.len()
+            )));
+        }
+        Ok(())
+    }
+
+    fn value(&self, name: &str) -> Option<&'a str> {
+        self.named
+            .iter()
+            .rev()
+            .find(|(option, _)| *option == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+fn set_estimate(app: &mut EstimateApp, id: &str, text: &str) -> Result<(), Error> {
+    let estimate = ThreePointEstimate::parse(text, &app.units)
+        .map_err(|error| Error::Failed(format!("Not a valid estimate {text:?}: {error}")))?;
+    let task = app
+        .find_mut_task(id)
+        .ok_or_else(|| Error::Failed(format!("No task with ID {id}")))?;
+    task.set_three_point(estimate);
+    Ok(())
+}
+
+fn save(path: &Path, app: &EstimateApp) -> Result<(), Error> {
+    document::save(path, app)
+        .map_err(|error| Error::Failed(format!("Could not save {}: {}", path.display(), error)))
+}
+
+/// One line per task, indented by depth: name, ID, estimate and, for parents, the rollup.
+fn tree(app: &EstimateApp) -> String {
+    let rollups = app.rollups();
+    let mut tree = format!("Root  {}\n", app.units.format(app.total()));
+    for task in app.get_tasks() {
+        write_tree(app, task, 1, &rollups, &mut tree);
+    }
+    tree
+}
+
+fn write_tree(
+    app: &EstimateApp,
+    task: &Task,
+    depth: usize,
+    rollups: &HashMap<String, Rollup>,
+    tree: &mut String,
+) {
+    let estimate = match task.three_point {
+        Some(range) => range.format(&app.units),
+        None => app.units.format(task.estimate),
+    };
+    tree.push_str(&format!(
+        "{}{} [{}]  {}",
+        "  ".repeat(depth),
+        task.name,
+        task.id,
+        estimate
+    ));
+    if let Some(rollup) = rollups.get(&task.id).filter(|_| !task.children.is_empty()) {
+        tree.push_str(&format!("  Σ {}", app.units.format(rollup.total)));
+        if rollup.conflict {
+            tree.push_str("  !");
+        }
+    }
+    tree.push('\n');
+    for child in &task.children {
+        write_tree(app, child, depth + 1, rollups, tree);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A document file in the temp directory, removed again when dropped.
+    struct TempDocument(std::path::PathBuf);
+
+    impl TempDocument {
+        fn new(name: &str, app: &EstimateApp) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "estimate-cli-{}-{}.{}",
+                std::process::id(),
+                name,
+                document::FILE_EXTENSION
+            ));
+            document::save(&path, app).unwrap();
+            Self(path)
+        }
+
+        fn run(&self, command: &str, args: &[&str]) -> Result<ExitCode, Error> {
+            let mut all = vec![command.to_owned(), self.0.display().to_string()];
+            all.extend(args.iter().map(|arg| arg.to_string()));
+            run(&all)
+        }
+
+        fn load(&self) -> EstimateApp {
+            document::load(&self.0).unwrap()
+        }
+    }
+
+    /// `ExitCode` has no `PartialEq`, so compare how it prints.
+    fn succeeded(code: ExitCode) -> bool {
+        format!("{:?}", code) == format!("{:?}", ExitCode::SUCCESS)
+    }
+
+    impl Drop for TempDocument {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn adds_tasks_and_sets_estimates() {
+        let file = TempDocument::new("add", &EstimateApp::new());
+        assert!(matches!(
+            file.run("add", &["Design"]),
+            Ok(code) if succeeded(code)
+        ));
+        let parent = file.load().get_tasks()[0].id.clone();
+        assert!(matches!(
+            file.run("add", &["Mockups", "--parent", &parent, "--estimate", "1d"]),
+            Ok(code) if succeeded(code)
+        ));
+        let child = file.load().get_tasks()[0].children[0].id.clone();
+        assert!(matches!(
+            file.run("set-estimate", &[&child, "2h/4h/8h"]),
+            Ok(code) if succeeded(code)
+        ));
+
+        let app = file.load();
+        let task = app.find_task(&child).unwrap();
+        assert_eq!(task.name, "Mockups");
+        assert_eq!(
+            task.three_point,
+            Some(ThreePointEstimate::new(2.0, 4.0, 8.0))
+        );
+        assert!(matches!(
+            file.run("set-estimate", &["missing", "4h"]),
+            Err(Error::Failed(_))
+        ));
+        assert!(matches!(
+            file.run("add", &["Orphan", "--parent", "missing"]),
+            Err(Error::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn reports_validation_problems_in_the_exit_status() {
+        let mut app = EstimateApp::new();
+        let task = app.add_task("Design");
+        let file = TempDocument::new("validate", &app);
+        // A leaf without an estimate is a problem.
+        assert!(matches!(file.run("validate", &[]), Ok(code) if !succeeded(code)));
+        assert!(matches!(
+            file.run("set-estimate", &[&task, "4h"]),
+            Ok(code) if succeeded(code)
+        ));
+        assert!(matches!(file.run("validate", &[]), Ok(code) if succeeded(code)));
+        assert!(matches!(file.run("total", &[]), Ok(code) if succeeded(code)));
+    }
+
+    #[test]
+    fn exports_to_a_file() {
+        let mut app = EstimateApp::new();
+        app.add_task("Design");
+        let file = TempDocument::new("export", &app);
+        let output = file.0.with_extension("md");
+        let output_arg = output.display().to_string();
+        assert!(matches!(
+            file.run("export", &["--format", "md", "--output", &output_arg]),
+            Ok(code) if succeeded(code)
+        ));
+        let markdown = std::fs::read_to_string(&output).unwrap();
+        let _ = std::fs::remove_file(&output);
+        assert!(markdown.contains("- Design (0h)"), "{markdown}");
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        let file = TempDocument::new("usage", &EstimateApp::new());
+        for (command, args) in [
+            ("frobnicate", &[][..]),
+            ("total", &["extra"][..]),
+            ("tree", &["--verbose", "yes"][..]),
+            ("add", &[][..]),
+            ("add", &["Name", "--estimate"][..]),
+            ("export", &[][..]),
+            ("export", &["--format", "docx"][..]),
+            ("export", &["--format", "svg", "--scale", "2"][..]),
+            ("export", &["--format", "png"][..]),
+        ] {
+            assert!(
+                matches!(file.run(command, args), Err(Error::Usage(_))),
+                "{command} {args:?}"
+            );
+        }
+        assert!(matches!(run(&[]), Err(Error::Usage(_))));
+        assert!(matches!(
+            run(&["total".to_owned(), "/nonexistent/document".to_owned()]),
+            Err(Error::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn prints_the_tree_with_rollups() {
+        let mut app = EstimateApp::new();
+        let phase = app.add_task("Phase");
+        app.add_child_task(&phase, "Build", 8.0).unwrap();
+        app.add_child_task(&phase, "Review", 4.0).unwrap();
+        assert_eq!(
+            tree(&app),
+            "Root  12h\n  Phase [task-0]  0h  Σ 12h\n    Build [task-1]  8h\n    Review [task-2]  4h\n"
+        );
+    }
+}
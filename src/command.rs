@@ -0,0 +1,346 @@
+use std::collections::VecDeque;
+
+use crate::{EstimateApp, EstimateUnits, LayoutStyle, RollupMode, Task, ThreePointEstimate};
+
+/// How many commands `History` keeps before dropping the oldest.
+pub const HISTORY_LIMIT: usize = 100;
+
+/// A single edit of an `EstimateApp`. Applying a command returns the command that undoes it.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub enum Command {
+    /// Inserts a task (with its children) under `parent_id`, or at the top level if `None`.
+    Insert {
+        parent_id: Option<String>,
+        index: Option<usize>,
+        task: Task,
+    },
+    Remove {
+        id: String,
+    },
+    /// Moves a task to `index` among the children of `parent_id`, or the top-level tasks if `None`.
+    Move {
+        id: String,
+        parent_id: Option<String>,
+        index: Option<usize>,
+    },
+    MoveUp {
+        id: String,
+    },
+    MoveDown {
+        id: String,
+    },
+    Indent {
+        id: String,
+    },
+    Outdent {
+        id: String,
+    },
+    SetEstimate {
+        id: String,
+        estimate: ThreePointEstimate,
+    },
+    Rename {
+        id: String,
+        name: String,
+    },
+    SetTaskRollupMode {
+        id: String,
+        mode: Option<RollupMode>,
+    },
+    SetRollupMode {
+        mode: RollupMode,
+    },
+    SetUnits {
+        units: EstimateUnits,
+    },
+    SetLayoutStyle {
+        style: LayoutStyle,
+    },
+    SetMaxNodeWidth {
+        width: f32,
+    },
+}
+
+impl Command {
+    /// Applies the command to `app` and returns its inverse,
+    /// or `None` if it could not be applied, in which case `app` is unchanged.
+    pub fn apply(self, app: &mut EstimateApp) -> Option<Command> {
+        match self {
+            Command::Insert {
+                parent_id,
+                index,
+                task,
+            } => {
+                let id = task.id.clone();
+                app.insert_task(parent_id.as_deref(), index, task)
+                    .then_some(Command::Remove { id })
+            }
+            Command::Remove { id } => {
+                let (parent_id, index) = app.task_position(&id)?;
+                let task = app.remove_task(&id)?;
+                Some(Command::Insert {
+                    parent_id,
+                    index: Some(index),
+                    task,
+                })
+            }
+            Command::Move {
+                id,
+                parent_id,
+                index,
+            } => Self::apply_move(app, id, |app, id| {
+                app.move_task_to(id, parent_id.as_deref(), index)
+            }),
+            Command::MoveUp { id } => Self::apply_move(app, id, EstimateApp::move_task_up),
+            Command::MoveDown { id } => Self::apply_move(app, id, EstimateApp::move_task_down),
+            Command::Indent { id } => Self::apply_move(app, id, EstimateApp::indent_task),
+            Command::Outdent { id } => Self::apply_move(app, id, EstimateApp::outdent_task),
+            Command::SetEstimate { id, estimate } => {
+                let task = app.find_mut_task(&id)?;
+                let previous = task.three_point_estimate();
+                task.set_three_point(estimate);
+                Some(Command::SetEstimate {
+                    id,
+                    estimate: previous,
+                })
+            }
+            Command::Rename { id, name } => {
+                let task = app.find_mut_task(&id)?;
+                let previous = std::mem::replace(&mut task.name, name);
+                Some(Command::Rename { id, name: previous })
+            }
+            Command::SetTaskRollupMode { id, mode } => {
+                let task = app.find_mut_task(&id)?;
+                let previous = std::mem::replace(&mut task.rollup_mode, mode);
+                Some(Command::SetTaskRollupMode { id, mode: previous })
+            }
+            Command::SetRollupMode { mode } => Some(Command::SetRollupMode {
+                mode: std::mem::replace(&mut app.rollup_mode, mode),
+            }),
+            Command::SetUnits { units } => Some(Command::SetUnits {
+                units: std::mem::replace(&mut app.units, units),
+            }),
+            Command::SetLayoutStyle { style } => Some(Command::SetLayoutStyle {
+                style: std::mem::replace(&mut app.layout_style, style),
+            }),
+            Command::SetMaxNodeWidth { width } => Some(Command::SetMaxNodeWidth {
+                width: std::mem::replace(&mut app.max_node_width, width),
+            }),
+        }
+    }
+
+    /// Runs a structural edit and returns a `Move` back to where the task was before.
+    fn apply_move(
+        app: &mut EstimateApp,
+        id: String,
+        edit: impl FnOnce(&mut EstimateApp, &str) -> bool,
+    ) -> Option<Command> {
+        let (parent_id, index) = app.task_position(&id)?;
+        edit(app, &id).then_some(Command::Move {
+            id,
+            parent_id,
+            index: Some(index),
+        })
+    }
+}
+
+/// Bounded undo/redo stacks of inverse commands.
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct History {
+    undo_stack: VecDeque<Command>,
+    redo_stack: Vec<Command>,
+}
+
+impl History {
+    /// Applies `command` and records its inverse. Returns `false` if nothing changed.
+    pub fn execute(&mut self, app: &mut EstimateApp, command: Command) -> bool {
+        let Some(inverse) = command.apply(app) else {
+            return false;
+        };
+        if self.undo_stack.len() == HISTORY_LIMIT {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(inverse);
+        self.redo_stack.clear();
+        true
+    }
+
+    /// Reverts the most recent command. Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self, app: &mut EstimateApp) -> bool {
+        let Some(inverse) = self.undo_stack.pop_back() else {
+            return false;
+        };
+        match inverse.apply(app) {
+            Some(redo) => {
+                self.redo_stack.push(redo);
+                true
+            }
+            // The document no longer matches the history, e.g. after loading a different one.
+            None => {
+                self.clear();
+                false
+            }
+        }
+    }
+
+    /// Re-applies the most recently undone command. Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self, app: &mut EstimateApp) -> bool {
+        let Some(command) = self.redo_stack.pop() else {
+            return false;
+        };
+        match command.apply(app) {
+            Some(inverse) => {
+                self.undo_stack.push_back(inverse);
+                true
+            }
+            None => {
+                self.clear();
+                false
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The saved shape of `app`, to compare documents.
+    fn snapshot(app: &EstimateApp) -> serde_json::Value {
+        serde_json::to_value(app).unwrap()
+    }
+
+    fn app_with_tasks() -> (EstimateApp, String, String) {
+        let mut app = EstimateApp::new();
+        let design = app.add_task("Design");
+        let build = app.add_task("Build");
+        app.add_child_task(&build, "Backend", 8.0).unwrap();
+        (app, design, build)
+    }
+
+    #[test]
+    fn applying_the_inverse_restores_the_document() {
+        let (app, design, build) = app_with_tasks();
+        let commands = [
+            Command::Insert {
+                parent_id: Some(build.clone()),
+                index: Some(0),
+                task: Task::new("new", "Frontend", 4.0),
+            },
+            Command::Remove { id: build.clone() },
+            Command::Move {
+                id: design.clone(),
+                parent_id: Some(build.clone()),
+                index: None,
+            },
+            Command::MoveDown { id: design.clone() },
+            Command::Indent { id: build.clone() },
+            Command::SetEstimate {
+                id: design.clone(),
+                estimate: ThreePointEstimate::new(2.0, 4.0, 8.0),
+            },
+            Command::Rename {
+                id: design.clone(),
+                name: "Sketch".into(),
+            },
+            Command::SetRollupMode {
+                mode: RollupMode::Max,
+            },
+            Command::SetMaxNodeWidth { width: 300.0 },
+        ];
+        for command in commands {
+            let mut edited = app.clone();
+            let description = format!("{command:?}");
+            let inverse = command.apply(&mut edited).expect(&description);
+            assert_ne!(snapshot(&edited), snapshot(&app), "{description}");
+            inverse.apply(&mut edited).expect(&description);
+            assert_eq!(snapshot(&edited), snapshot(&app), "{description}");
+        }
+    }
+
+    #[test]
+    fn commands_that_do_not_apply_change_nothing() {
+        let (mut app, design, _) = app_with_tasks();
+        let before = snapshot(&app);
+        assert!(Command::Remove {
+            id: "missing".into()
+        }
+        .apply(&mut app)
+        .is_none());
+        assert!(Command::MoveUp { id: design.clone() }
+            .apply(&mut app)
+            .is_none());
+        assert!(Command::Outdent { id: design }.apply(&mut app).is_none());
+        assert_eq!(snapshot(&app), before);
+    }
+
+    #[test]
+    fn undoes_and_redoes_in_order() {
+        let (mut app, design, _) = app_with_tasks();
+        let mut history = History::default();
+        let original = snapshot(&app);
+        assert!(!history.can_undo());
+
+        let rename = Command::Rename {
+            id: design.clone(),
+            name: "Sketch".into(),
+        };
+        assert!(history.execute(&mut app, rename));
+        let renamed = snapshot(&app);
+        assert!(history.execute(&mut app, Command::Remove { id: design }));
+        assert!(!history.execute(
+            &mut app,
+            Command::Remove {
+                id: "missing".into()
+            }
+        ));
+
+        assert!(history.undo(&mut app));
+        assert_eq!(snapshot(&app), renamed);
+        assert!(history.undo(&mut app));
+        assert_eq!(snapshot(&app), original);
+        assert!(!history.undo(&mut app));
+
+        assert!(history.redo(&mut app));
+        assert_eq!(snapshot(&app), renamed);
+        assert!(history.can_redo());
+        // A new edit drops what could have been redone.
+        history.execute(&mut app, Command::SetMaxNodeWidth { width: 300.0 });
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut app));
+    }
+
+    #[test]
+    fn keeps_only_the_most_recent_commands() {
+        let mut app = EstimateApp::new();
+        let mut history = History::default();
+        for width in 0..HISTORY_LIMIT + 5 {
+            let command = Command::SetMaxNodeWidth {
+                width: 100.0 + width as f32,
+            };
+            assert!(history.execute(&mut app, command));
+        }
+
+        let mut undone = 0;
+        while history.undo(&mut app) {
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_LIMIT);
+        // The oldest commands were dropped, so their effect stays.
+        assert_eq!(app.max_node_width, 104.0);
+    }
+}
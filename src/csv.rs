@@ -0,0 +1,153 @@
+//! Spreadsheet export with one row per task, in the pre-order of `EstimateApp::flatten_tasks`.
+
+use std::collections::HashMap;
+
+use crate::{import::PATH_SEPARATOR, EstimateApp, EstimateUnit, Rollup, Task};
+
+/// Writes the document as CSV with a header row and the columns
+/// `wbs, path, depth, estimate, rollup, leaf`.
+/// Estimates are plain numbers in hours, or story points for documents estimated in points,
+/// so spreadsheets can sum them directly.
+pub fn to_csv(app: &EstimateApp) -> String {
+    let unit = if app.units.unit == EstimateUnit::StoryPoints {
+        "points"
+    } else {
+        "hours"
+    };
+    let mut csv = format!("wbs,path,depth,estimate_{unit},rollup_{unit},leaf\n");
+    let rollups = app.rollups();
+    let mut path = Vec::new();
+    for (index, task) in app.get_tasks().iter().enumerate() {
+        write_rows(
+            task,
+            &(index + 1).to_string(),
+            &mut path,
+            &rollups,
+            &mut csv,
+        );
+    }
+    csv
+}
+
+fn write_rows<'a>(
+    task: &'a Task,
+    wbs: &str,
+    path: &mut Vec<&'a str>,
+    rollups: &HashMap<String, Rollup>,
+    csv: &mut String,
+) {
+    path.push(&task.name);
+    let rollup = rollups
+        .get(&task.id)
+        .map_or(task.estimate, |rollup| rollup.total);
+    let row = [
+        wbs.to_owned(),
+        path.join(PATH_SEPARATOR),
+        (path.len() - 1).to_string(),
+        task.estimate.to_string(),
+        rollup.to_string(),
+        task.children.is_empty().to_string(),
+    ];
+    let row: Vec<String> = row.iter().map(|field| escape(field)).collect();
+    csv.push_str(&row.join(","));
+    csv.push('\n');
+
+    for (index, child) in task.children.iter().enumerate() {
+        write_rows(child, &format!("{}.{}", wbs, index + 1), path, rollups, csv);
+    }
+    path.pop();
+}
+
+/// Quotes a field if it contains a comma, quote or line break, as RFC 4180 requires.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits RFC 4180 text into records, undoing `escape`.
+    fn parse(csv: &str) -> Vec<Vec<String>> {
+        let mut records = Vec::new();
+        let mut record = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = csv.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (quoted, c) {
+                (true, '"') if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                (true, '"') => quoted = false,
+                (true, c) => field.push(c),
+                (false, '"') => quoted = true,
+                (false, ',') => record.push(std::mem::take(&mut field)),
+                (false, '\n') => {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                (false, c) => field.push(c),
+            }
+        }
+        records
+    }
+
+    #[test]
+    fn rows_read_back_as_the_tasks() {
+        let mut app = EstimateApp::new();
+        let phase = app.add_task("Phase 1, \"MVP\"");
+        app.add_child_task(&phase, "Build\nand ship", 1.0 / 3.0)
+            .unwrap();
+        app.add_child_task(&phase, "Review", 2.0).unwrap();
+        app.add_task("Launch");
+
+        let records = parse(&to_csv(&app));
+        assert_eq!(
+            records[0],
+            [
+                "wbs",
+                "path",
+                "depth",
+                "estimate_hours",
+                "rollup_hours",
+                "leaf"
+            ]
+        );
+        let rows: Vec<Vec<&str>> = records[1..]
+            .iter()
+            .map(|record| record.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(rows.len(), app.flatten_tasks().len());
+        assert_eq!(rows[0][..3], ["1", "Phase 1, \"MVP\"", "0"]);
+        assert_eq!(
+            rows[1][..3],
+            ["1.1", "Phase 1, \"MVP\" / Build\nand ship", "1"]
+        );
+        assert_eq!(rows[2][..3], ["1.2", "Phase 1, \"MVP\" / Review", "1"]);
+        assert_eq!(rows[3][..3], ["2", "Launch", "0"]);
+
+        let rollups = app.rollups();
+        for (row, task) in rows.iter().zip(app.flatten_tasks()) {
+            assert_eq!(row[3].parse::<f32>(), Ok(task.estimate));
+            assert_eq!(row[4].parse::<f32>(), Ok(rollups[&task.id].total));
+            assert_eq!(row[5], task.children.is_empty().to_string());
+        }
+    }
+
+    #[test]
+    fn names_the_columns_after_the_unit() {
+        let mut app = EstimateApp::new();
+        app.units.unit = EstimateUnit::StoryPoints;
+        let header = to_csv(&app);
+        assert_eq!(
+            header,
+            "wbs,path,depth,estimate_points,rollup_points,leaf\n"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use serde_json::Value;
+
+use crate::EstimateApp;
+
+/// Upgrades a parsed document by one format version.
+type Migration = fn(Value) -> Result<Value, DocumentError>;
+
+/// `MIGRATIONS[n]` upgrades a document from format version `n` to `n + 1`.
+/// When the saved shape of `EstimateApp` changes, append a migration here;
+/// `FORMAT_VERSION` follows automatically.
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+/// Version written by `to_json`.
+pub const FORMAT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// File extension of saved mindmap documents.
+pub const FILE_EXTENSION: &str = "mindmap.json";
+
+/// The on-disk shape of a mindmap document.
+#[derive(serde::Deserialize, serde::Serialize)]
+struct Document {
+    version: u32,
+    estimate_app: EstimateApp,
+}
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The document was written by a newer version of the app.
+    UnsupportedVersion(u32),
+    /// A migration could not make sense of a document of the given version.
+    Migration(u32, String),
+}
+
+impl std::fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentError::Io(error) => write!(f, "Could not access the file: {}", error),
+            DocumentError::Json(error) => write!(f, "Not a valid mindmap document: {}", error),
+            DocumentError::UnsupportedVersion(version) => write!(
+                f,
+                "The document has format version {}, this app only reads up to version {}",
+                version, FORMAT_VERSION
+            ),
+            DocumentError::Migration(version, reason) => write!(
+                f,
+                "Could not upgrade the document from format version {}: {}",
+                version, reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl From<std::io::Error> for DocumentError {
+    fn from(error: std::io::Error) -> Self {
+        DocumentError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(error: serde_json::Error) -> Self {
+        DocumentError::Json(error)
+    }
+}
+
+/// Serializes `app` as a versioned, pretty-printed JSON document.
+pub fn to_json(app: &EstimateApp) -> Result<String, DocumentError> {
+    #[derive(serde::Serialize)]
+    struct DocumentRef<'a> {
+        version: u32,
+        estimate_app: &'a EstimateApp,
+    }
+    Ok(serde_json::to_string_pretty(&DocumentRef {
+        version: FORMAT_VERSION,
+        estimate_app: app,
+    })?)
+}
+
+/// A document read by `from_json`.
+#[derive(Debug)]
+pub struct LoadedDocument {
+    pub estimate_app: EstimateApp,
+    /// Tasks that had a duplicate or missing ID and were given a new one.
+    /// Anything referring to tasks by ID, like a saved undo history, may no longer fit.
+    pub repaired_ids: usize,
+}
+
+/// Parses a document written by `to_json` by any version of the app,
+/// migrating it to the current format and repairing duplicate task IDs.
+pub fn from_json(text: &str) -> Result<LoadedDocument, DocumentError> {
+    from_value(serde_json::from_str(text)?)
+}
+
+/// Like `from_json`, for a document that has already been parsed.
+pub fn from_value(mut value: Value) -> Result<LoadedDocument, DocumentError> {
+    // Documents from before versioning are a bare `EstimateApp` without a version field,
+    // as in the app state of old versions, see `migrate_v0_to_v1`.
+    let version = match value.get("version") {
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| DocumentError::Migration(0, "the version is not a number".into()))?,
+        None => 0,
+    };
+    if version > FORMAT_VERSION {
+        return Err(DocumentError::UnsupportedVersion(version));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        value = migration(value)?;
+    }
+
+    let document: Document = serde_json::from_value(value)?;
+    let mut estimate_app = document.estimate_app;
+    let repaired_ids = estimate_app.repair_task_ids();
+    if repaired_ids > 0 {
+        log::warn!("Gave {} tasks with duplicate IDs a new ID", repaired_ids);
+    }
+    Ok(LoadedDocument {
+        estimate_app,
+        repaired_ids,
+    })
+}
+
+/// Wraps an unversioned `EstimateApp` in the versioned document envelope.
+/// Anything without a `tasks` list is some other JSON file, not an old document.
+fn migrate_v0_to_v1(value: Value) -> Result<Value, DocumentError> {
+    if !value.get("tasks").is_some_and(Value::is_array) {
+        return Err(DocumentError::Migration(
+            0,
+            "not a mindmap document, it has neither a version nor a list of tasks".into(),
+        ));
+    }
+    Ok(serde_json::json!({
+        "version": 1,
+        "estimate_app": value,
+    }))
+}
+
+pub fn save(path: &std::path::Path, app: &EstimateApp) -> Result<(), DocumentError> {
+    std::fs::write(path, to_json(app)?)?;
+    Ok(())
+}
+
+pub fn load(path: &std::path::Path) -> Result<EstimateApp, DocumentError> {
+    Ok(from_json(&std::fs::read_to_string(path)?)?.estimate_app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_an_unversioned_document() {
+        let value = serde_json::json!({
+            "tasks": [{ "id": "a", "name": "Design", "estimate": 4.0, "children": [] }],
+            "units": { "unit": "Days", "hours_per_day": 6.0 },
+        });
+
+        let loaded = from_value(value).unwrap();
+        assert_eq!(loaded.repaired_ids, 0);
+        let json = to_json(&loaded.estimate_app).unwrap();
+        assert!(json.contains(&format!("\"version\": {}", FORMAT_VERSION)));
+
+        let reloaded = from_json(&json).unwrap().estimate_app;
+        let tasks = reloaded.get_tasks();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, "a");
+        assert_eq!(tasks[0].name, "Design");
+        assert_eq!(tasks[0].estimate, 4.0);
+        assert_eq!(reloaded.units, loaded.estimate_app.units);
+        assert_eq!(reloaded.units.hours_per_day, 6.0);
+    }
+
+    #[test]
+    fn rejects_other_json_files() {
+        let tsconfig = r#"{ "compilerOptions": { "strict": true } }"#;
+        assert!(matches!(
+            from_json(tsconfig),
+            Err(DocumentError::Migration(0, _))
+        ));
+        assert!(matches!(
+            from_json("[1, 2, 3]"),
+            Err(DocumentError::Migration(0, _))
+        ));
+    }
+
+    #[test]
+    fn rejects_documents_from_newer_versions() {
+        let text = format!(
+            r#"{{ "version": {}, "estimate_app": {{ "tasks": [] }} }}"#,
+            FORMAT_VERSION + 1
+        );
+        assert!(matches!(
+            from_json(&text),
+            Err(DocumentError::UnsupportedVersion(version)) if version == FORMAT_VERSION + 1
+        ));
+    }
+}
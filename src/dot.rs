@@ -0,0 +1,102 @@
+//! Exports the task tree as a Graphviz DOT digraph, e.g. for `dot -Tsvg`.
+
+use std::collections::HashMap;
+
+use crate::label::estimate_summary;
+use crate::{EstimateApp, Rollup, Task};
+
+/// ID of the node standing for the whole document. Task nodes are named by `node_id`,
+/// so no task ID can collide with it.
+const ROOT_ID: &str = "root";
+
+/// Writes one box per task, labelled with its name, estimate and rollup, with an edge from
+/// every parent to its children. Colors follow the canvas: subtasks are gray and tasks whose
+/// estimate conflicts with their children have an orange outline.
+pub fn to_dot(app: &EstimateApp) -> String {
+    let rollups = app.rollups();
+    let mut dot = String::from("digraph estimates {\n");
+    dot.push_str("  rankdir=LR;\n");
+    dot.push_str(
+        "  node [shape=box, style=\"rounded,filled\", fillcolor=white, fontname=\"sans-serif\"];\n",
+    );
+    dot.push_str(&format!(
+        "  {} [label={}];\n",
+        quote(ROOT_ID),
+        quote(&format!("Root\n{}", app.units.format(app.total())))
+    ));
+    for task in app.get_tasks() {
+        write_node(app, task, ROOT_ID, 0, &rollups, &mut dot);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn write_node(
+    app: &EstimateApp,
+    task: &Task,
+    parent_id: &str,
+    depth: usize,
+    rollups: &HashMap<String, Rollup>,
+    dot: &mut String,
+) {
+    let rollup = rollups.get(&task.id);
+    let label = format!(
+        "{}\n{}",
+        task.name,
+        estimate_summary(task, rollup, &app.units)
+    );
+    let id = quote(&node_id(&task.id));
+    dot.push_str(&format!("  {} [label={}", id, quote(&label)));
+    if rollup.is_some_and(|rollup| rollup.conflict) {
+        dot.push_str(", color=orange, penwidth=2");
+    }
+    if depth > 0 {
+        dot.push_str(", fillcolor=lightgray");
+    }
+    dot.push_str("];\n");
+    dot.push_str(&format!("  {} -> {};\n", quote(parent_id), id));
+    for child in &task.children {
+        write_node(app, child, &node_id(&task.id), depth + 1, rollups, dot);
+    }
+}
+
+/// The DOT node ID of the task `task_id`, kept apart from `ROOT_ID` by its prefix.
+fn node_id(task_id: &str) -> String {
+    format!("t_{}", task_id)
+}
+
+/// A DOT string literal, with line breaks as centered `\n` escapes.
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_task_nodes_apart_from_the_root() {
+        let mut app = EstimateApp::new();
+        let mut task = Task::new("root", "Tricky \"name\"", 4.0);
+        task.children.push(Task::new("_root", "Child", 4.0));
+        app.get_tasks_mut().push(task);
+
+        let dot = to_dot(&app);
+        assert!(dot.contains("  \"root\" [label=\"Root\\n"), "{dot}");
+        assert!(dot.contains("  \"root\" -> \"t_root\";\n"), "{dot}");
+        assert!(dot.contains("  \"t_root\" -> \"t__root\";\n"), "{dot}");
+        assert!(dot.contains("label=\"Tricky \\\"name\\\"\\n"), "{dot}");
+        assert_eq!(dot.matches(" -> ").count(), 2, "{dot}");
+    }
+}
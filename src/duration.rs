@@ -0,0 +1,270 @@
+const DAYS_PER_WEEK: f32 = 5.0;
+
+/// The unit an estimate is entered or shown in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum EstimateUnit {
+    #[default]
+    Hours,
+    Days,
+    Weeks,
+    StoryPoints,
+}
+
+impl EstimateUnit {
+    pub const ALL: [EstimateUnit; 4] = [
+        EstimateUnit::Hours,
+        EstimateUnit::Days,
+        EstimateUnit::Weeks,
+        EstimateUnit::StoryPoints,
+    ];
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            EstimateUnit::Hours => "h",
+            EstimateUnit::Days => "d",
+            EstimateUnit::Weeks => "w",
+            EstimateUnit::StoryPoints => "sp",
+        }
+    }
+
+    pub fn is_time(&self) -> bool {
+        *self != EstimateUnit::StoryPoints
+    }
+
+    pub(crate) fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix.to_lowercase().as_str() {
+            "h" | "hr" | "hrs" | "hour" | "hours" => Some(EstimateUnit::Hours),
+            "d" | "day" | "days" => Some(EstimateUnit::Days),
+            "w" | "wk" | "wks" | "week" | "weeks" => Some(EstimateUnit::Weeks),
+            "sp" | "pt" | "pts" | "point" | "points" => Some(EstimateUnit::StoryPoints),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for EstimateUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EstimateUnit::Hours => "Hours",
+            EstimateUnit::Days => "Days",
+            EstimateUnit::Weeks => "Weeks",
+            EstimateUnit::StoryPoints => "Story points",
+        })
+    }
+}
+
+/// A value together with the unit it was written in, e.g. `2d` or `4h`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Duration {
+    pub value: f32,
+    pub unit: EstimateUnit,
+}
+
+impl Duration {
+    pub fn new(value: f32, unit: EstimateUnit) -> Self {
+        Self { value, unit }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DurationParseError {
+    Empty,
+    InvalidNumber(String),
+    UnknownUnit(String),
+    /// More than one factor of a product had a unit, e.g. `2d*3h`.
+    UnitProduct(String),
+    /// Story points were mixed with time units.
+    IncompatibleUnit(EstimateUnit),
+}
+
+impl std::fmt::Display for DurationParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DurationParseError::Empty => write!(f, "Enter an estimate, e.g. \"2d 4h\""),
+            DurationParseError::InvalidNumber(text) => write!(f, "\"{}\" is not a number", text),
+            DurationParseError::UnknownUnit(text) => write!(f, "Unknown unit \"{}\"", text),
+            DurationParseError::UnitProduct(text) => {
+                write!(f, "\"{}\" multiplies two values with units", text)
+            }
+            DurationParseError::IncompatibleUnit(unit) => {
+                write!(f, "{} cannot be mixed with this document's unit", unit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DurationParseError {}
+
+/// Unit settings of a document.
+///
+/// `Task::estimate` is stored in hours when `unit` is a time unit, and in story points otherwise.
+/// `unit` is used both for display and for numbers entered without a suffix.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct EstimateUnits {
+    pub unit: EstimateUnit,
+    pub hours_per_day: f32,
+}
+
+impl Default for EstimateUnits {
+    fn default() -> Self {
+        Self {
+            unit: EstimateUnit::Hours,
+            hours_per_day: 8.0,
+        }
+    }
+}
+
+impl EstimateUnits {
+    /// How many stored units one `unit` is worth.
+    fn base_factor(&self, unit: EstimateUnit) -> Result<f32, DurationParseError> {
+        if unit.is_time() != self.unit.is_time() {
+            return Err(DurationParseError::IncompatibleUnit(unit));
+        }
+        Ok(match unit {
+            EstimateUnit::Hours | EstimateUnit::StoryPoints => 1.0,
+            EstimateUnit::Days => self.hours_per_day,
+            EstimateUnit::Weeks => self.hours_per_day * DAYS_PER_WEEK,
+        })
+    }
+
+    /// Converts a duration to the stored unit (hours or story points).
+    pub fn to_base(&self, duration: Duration) -> Result<f32, DurationParseError> {
+        Ok(duration.value * self.base_factor(duration.unit)?)
+    }
+
+    /// Parses text such as `"2d 4h"`, `"1.5w"`, `"3*4"` or `"2d + 4"` into the stored unit.
+    /// Terms are separated by whitespace or `+` and summed; numbers without a suffix use `self.unit`.
+    pub fn parse(&self, text: &str) -> Result<f32, DurationParseError> {
+        let terms: Vec<&str> = text
+            .split(|c: char| c.is_whitespace() || c == '+')
+            .filter(|term| !term.is_empty())
+            .collect();
+        if terms.is_empty() {
+            return Err(DurationParseError::Empty);
+        }
+        terms.into_iter().try_fold(0.0, |sum, term| {
+            Ok(sum + self.to_base(parse_product(term, self.unit)?)?)
+        })
+    }
+
+    /// Formats a stored value in the display unit, e.g. `"1.5d"`.
+    pub fn format(&self, base: f32) -> String {
+        let factor = self.base_factor(self.unit).unwrap_or(1.0);
+        format!(
+            "{}{}",
+            format_number(base / factor.max(f32::EPSILON)),
+            self.unit.suffix()
+        )
+    }
+
+    /// Like `format`, but when the display unit would round the value it is written in
+    /// hours (or story points) at full precision instead, so `parse` reads back exactly `base`.
+    pub fn format_exact(&self, base: f32) -> String {
+        let formatted = self.format(base);
+        if self.parse(&formatted) == Ok(base) {
+            return formatted;
+        }
+        let base_unit = if self.unit.is_time() {
+            EstimateUnit::Hours
+        } else {
+            EstimateUnit::StoryPoints
+        };
+        format!("{}{}", base, base_unit.suffix())
+    }
+}
+
+/// Parses a single term such as `"3*4"`, `"2d"` or `"0.5*3w"`. At most one factor may have a unit.
+fn parse_product(term: &str, default_unit: EstimateUnit) -> Result<Duration, DurationParseError> {
+    let mut value = 1.0;
+    let mut unit = None;
+    for factor in term.split('*') {
+        let split = factor
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(factor.len());
+        let (number, suffix) = factor.split_at(split);
+        value *= number
+            .parse::<f32>()
+            .map_err(|_| DurationParseError::InvalidNumber(factor.to_owned()))?;
+        if !suffix.is_empty() {
+            if unit.is_some() {
+                return Err(DurationParseError::UnitProduct(term.to_owned()));
+            }
+            unit = Some(
+                EstimateUnit::from_suffix(suffix)
+                    .ok_or_else(|| DurationParseError::UnknownUnit(suffix.to_owned()))?,
+            );
+        }
+    }
+    Ok(Duration::new(value, unit.unwrap_or(default_unit)))
+}
+
+/// Shows whole numbers without decimals and everything else with at most two.
+fn format_number(value: f32) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days() -> EstimateUnits {
+        EstimateUnits {
+            unit: EstimateUnit::Days,
+            hours_per_day: 8.0,
+        }
+    }
+
+    #[test]
+    fn parses_compound_product_and_sum_input() {
+        let units = days();
+        assert_eq!(units.parse("2d 4h"), Ok(20.0));
+        assert_eq!(units.parse("1.5w"), Ok(60.0));
+        assert_eq!(units.parse("3*4"), Ok(96.0));
+        assert_eq!(units.parse("0.5*3w"), Ok(60.0));
+        assert_eq!(units.parse("2d + 4"), Ok(48.0));
+        assert_eq!(units.parse("1d+2h"), Ok(10.0));
+        // Numbers without a suffix are in the display unit.
+        assert_eq!(units.parse("2"), Ok(16.0));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        let units = days();
+        assert_eq!(units.parse(""), Err(DurationParseError::Empty));
+        assert_eq!(units.parse(" + "), Err(DurationParseError::Empty));
+        assert_eq!(
+            units.parse("abc"),
+            Err(DurationParseError::InvalidNumber("abc".into()))
+        );
+        assert_eq!(
+            units.parse("2x"),
+            Err(DurationParseError::UnknownUnit("x".into()))
+        );
+        assert_eq!(
+            units.parse("2d*3h"),
+            Err(DurationParseError::UnitProduct("2d*3h".into()))
+        );
+        assert_eq!(
+            units.parse("3sp"),
+            Err(DurationParseError::IncompatibleUnit(
+                EstimateUnit::StoryPoints
+            ))
+        );
+    }
+
+    #[test]
+    fn exact_format_parses_back_to_the_same_value() {
+        let units = days();
+        assert_eq!(units.format(1.0), "0.13d");
+        assert_eq!(units.format_exact(16.0), "2d");
+        for base in [1.0, 16.0, 100.0 / 3.0, 0.1] {
+            assert_eq!(units.parse(&units.format_exact(base)), Ok(base));
+        }
+    }
+}
@@ -1,17 +1,59 @@
-use crate::Task;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+use crate::{
+    duration::EstimateUnits,
+    layout::LayoutStyle,
+    pert::PertSummary,
+    rollup::{Rollup, RollupMode, RollupSource},
+    Task,
+};
+
+/// Own and children estimates further apart than this are flagged as a conflict.
+const CONFLICT_TOLERANCE: f32 = 0.01;
+
+/// Widest a task's box gets before its name is wrapped, unless the document says otherwise.
+pub const DEFAULT_MAX_NODE_WIDTH: f32 = 240.0;
+
+/// Changes to the saved shape of this or of `Task` need a migration in `document.rs`.
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)] // fields missing from a saved document get their default value
 pub struct EstimateApp {
     pub tasks: Vec<Task>,
+    pub units: EstimateUnits,
+    /// Default for tasks that don't set `Task::rollup_mode`.
+    pub rollup_mode: RollupMode,
+    pub layout_style: LayoutStyle,
+    /// Task names wider than this are wrapped, up to a few lines, then cut off.
+    pub max_node_width: f32,
+    /// Counter behind `generate_task_id`, persisted so IDs are never handed out twice.
+    next_task_id: u64,
+}
+
+impl Default for EstimateApp {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EstimateApp {
     pub fn new() -> Self {
-        Self { tasks: Vec::new() }
+        Self {
+            tasks: Vec::new(),
+            units: EstimateUnits::default(),
+            rollup_mode: RollupMode::default(),
+            layout_style: LayoutStyle::default(),
+            max_node_width: DEFAULT_MAX_NODE_WIDTH,
+            next_task_id: 0,
+        }
     }
     pub fn new_example_app() -> Self {
         Self {
             tasks: Self::get_example_tasks(),
+            units: EstimateUnits::default(),
+            rollup_mode: RollupMode::default(),
+            layout_style: LayoutStyle::default(),
+            max_node_width: DEFAULT_MAX_NODE_WIDTH,
+            next_task_id: 0,
         }
     }
     fn get_example_tasks() -> Vec<Task> {
@@ -28,27 +70,31 @@ impl EstimateApp {
                     Task::new(
                         format!("task-{}-1", count).as_str(),
                         format!("Example Task {} 1", count).as_str(),
-                        4,
+                        4.0,
                     ),
                     Task::new(
                         format!("task-{}-2", count).as_str(),
                         format!("Example Task {} 2", count).as_str(),
-                        4,
+                        4.0,
                     ),
                     Task::new(
                         format!("task-{}-3", count).as_str(),
                         format!("Example Task {} 3", count).as_str(),
-                        4,
+                        4.0,
                     ),
                 ],
-                estimate: 16,
+                estimate: 16.0,
+                three_point: None,
+                rollup_mode: None,
                 id: format!("task-{}", count),
                 name: format!("Example task {}", count),
             }
         } else {
             Task {
                 children: Vec::new(),
-                estimate: 16,
+                estimate: 16.0,
+                three_point: None,
+                rollup_mode: None,
                 id: format!("task-{}", count),
                 name: format!("Example task {}", count),
             }
@@ -60,13 +106,146 @@ impl EstimateApp {
     pub fn get_tasks(&self) -> &Vec<Task> {
         &self.tasks
     }
+    pub fn rollup_mode_for(&self, task: &Task) -> RollupMode {
+        task.rollup_mode.unwrap_or(self.rollup_mode)
+    }
+
+    /// Computes the rolled-up estimate of every task in the document, keyed by task id.
+    pub fn rollups(&self) -> HashMap<String, Rollup> {
+        let mut rollups = HashMap::new();
+        for task in &self.tasks {
+            self.rollup_recursive(task, &mut rollups);
+        }
+        rollups
+    }
+
+    /// Rolled-up estimate of the whole document, the sum of all top-level tasks.
+    pub fn total(&self) -> f32 {
+        let mut rollups = HashMap::new();
+        self.tasks
+            .iter()
+            .map(|task| self.rollup_recursive(task, &mut rollups))
+            .sum()
+    }
+
+    /// Inserts the rollups of `task` and its subtree and returns the total of `task`.
+    fn rollup_recursive(&self, task: &Task, rollups: &mut HashMap<String, Rollup>) -> f32 {
+        let children: f32 = task
+            .children
+            .iter()
+            .map(|child| self.rollup_recursive(child, rollups))
+            .sum();
+        let mode = self.rollup_mode_for(task);
+        let has_children = !task.children.is_empty();
+        let rollup = Rollup {
+            children,
+            total: mode.combine(task.estimate, children, has_children),
+            conflict: has_children
+                && mode.own_competes_with_children()
+                && task.estimate > 0.0
+                && (task.estimate - children).abs() > CONFLICT_TOLERANCE,
+        };
+        rollups.insert(task.id.clone(), rollup);
+        rollup.total
+    }
+
+    /// PERT expected value and variance of `task` and its subtree, following the rollup mode.
+    pub fn pert_rollup(&self, task: &Task) -> PertSummary {
+        let own = PertSummary::from(task.three_point_estimate());
+        let children: PertSummary = task
+            .children
+            .iter()
+            .map(|child| self.pert_rollup(child))
+            .sum();
+        match self.rollup_mode_for(task).source(
+            own.expected,
+            children.expected,
+            !task.children.is_empty(),
+        ) {
+            RollupSource::Own => own,
+            RollupSource::Children => children,
+            RollupSource::Both => own + children,
+        }
+    }
+
+    /// PERT expected value and variance of all tasks in the document.
+    pub fn pert_total(&self) -> PertSummary {
+        self.tasks.iter().map(|task| self.pert_rollup(task)).sum()
+    }
     pub fn add_task(&mut self, name: &str) -> String {
-        let task = Task::new(&format!("task-{}", self.tasks.len()), name, 0);
+        let task = Task::new(&self.generate_task_id(), name, 0.0);
         let id = task.id.clone();
         self.tasks.push(task);
         id
     }
 
+    /// Adds a child to the task with `parent_id` and returns the new ID,
+    /// or `None` if there is no such parent.
+    pub fn add_child_task(&mut self, parent_id: &str, name: &str, estimate: f32) -> Option<String> {
+        let id = self.generate_task_id();
+        let parent = self.find_mut_task(parent_id)?;
+        Some(parent.add_child_task(id, name, estimate))
+    }
+
+    /// Returns an ID that no task in the document uses yet.
+    pub fn generate_task_id(&mut self) -> String {
+        let used = self.task_ids();
+        Self::next_free_id(&mut self.next_task_id, &used)
+    }
+
+    fn next_free_id(counter: &mut u64, used: &HashSet<String>) -> String {
+        loop {
+            let candidate = format!("task-{}", counter);
+            *counter += 1;
+            if !used.contains(&candidate) {
+                return candidate;
+            }
+        }
+    }
+
+    fn task_ids(&self) -> HashSet<String> {
+        self.flatten_tasks()
+            .into_iter()
+            .map(|task| task.id.clone())
+            .collect()
+    }
+
+    /// Gives every task with an empty or duplicate ID a fresh one, keeping the first occurrence.
+    /// Call this after loading a document. Returns how many tasks were changed.
+    pub fn repair_task_ids(&mut self) -> usize {
+        let mut used = self.task_ids();
+        let mut seen = HashSet::new();
+        let mut repaired = 0;
+        for task in &mut self.tasks {
+            Self::repair_task_ids_recursive(
+                task,
+                &mut seen,
+                &mut used,
+                &mut self.next_task_id,
+                &mut repaired,
+            );
+        }
+        repaired
+    }
+
+    fn repair_task_ids_recursive(
+        task: &mut Task,
+        seen: &mut HashSet<String>,
+        used: &mut HashSet<String>,
+        counter: &mut u64,
+        repaired: &mut usize,
+    ) {
+        if task.id.is_empty() || !seen.insert(task.id.clone()) {
+            task.id = Self::next_free_id(counter, used);
+            used.insert(task.id.clone());
+            seen.insert(task.id.clone());
+            *repaired += 1;
+        }
+        for child in &mut task.children {
+            Self::repair_task_ids_recursive(child, seen, used, counter, repaired);
+        }
+    }
+
     /// Searches the tasks tree for a task matching the given `id` and returns a reference if found.
     pub fn find_task(&self, id: &str) -> Option<&Task> {
         for task in &self.tasks {
@@ -119,6 +298,149 @@ impl EstimateApp {
         None
     }
 
+    /// Returns the ID of the parent of the task with `id`, or `None` for top-level and unknown tasks.
+    pub fn parent_id(&self, id: &str) -> Option<String> {
+        self.flatten_tasks()
+            .into_iter()
+            .find(|task| task.children.iter().any(|child| child.id == id))
+            .map(|task| task.id.clone())
+    }
+
+    /// Finds the list that contains the task with `id`, and the task's index in it.
+    fn find_siblings_mut(&mut self, id: &str) -> Option<(&mut Vec<Task>, usize)> {
+        Self::find_siblings_recursive(&mut self.tasks, id)
+    }
+
+    /// Helper function that recursively searches `tasks` and their children for the list containing `id`.
+    fn find_siblings_recursive<'a>(
+        tasks: &'a mut Vec<Task>,
+        id: &str,
+    ) -> Option<(&'a mut Vec<Task>, usize)> {
+        if let Some(index) = tasks.iter().position(|task| task.id == id) {
+            return Some((tasks, index));
+        }
+        for task in tasks.iter_mut() {
+            if let Some(found) = Self::find_siblings_recursive(&mut task.children, id) {
+                return Some(found);
+            }
+        }
+        None
+    }
+
+    /// Returns the parent ID (`None` for top-level tasks) and index among siblings of the task with `id`.
+    pub fn task_position(&self, id: &str) -> Option<(Option<String>, usize)> {
+        let parent_id = self.parent_id(id);
+        let siblings = match &parent_id {
+            Some(parent_id) => &self.find_task(parent_id)?.children,
+            None => &self.tasks,
+        };
+        let index = siblings.iter().position(|task| task.id == id)?;
+        Some((parent_id, index))
+    }
+
+    /// Inserts `task` at `index` among the children of `parent_id`, or among the top-level tasks if `None`.
+    /// An `index` of `None` or past the end appends. Returns `false` if the parent does not exist.
+    pub fn insert_task(
+        &mut self,
+        parent_id: Option<&str>,
+        index: Option<usize>,
+        task: Task,
+    ) -> bool {
+        let siblings = match parent_id {
+            Some(parent_id) => match self.find_mut_task(parent_id) {
+                Some(parent) => &mut parent.children,
+                None => return false,
+            },
+            None => &mut self.tasks,
+        };
+        let index = index.unwrap_or(siblings.len()).min(siblings.len());
+        siblings.insert(index, task);
+        true
+    }
+
+    /// Removes the task with `id` together with all of its children and returns it.
+    pub fn remove_task(&mut self, id: &str) -> Option<Task> {
+        let (siblings, index) = self.find_siblings_mut(id)?;
+        Some(siblings.remove(index))
+    }
+
+    /// Moves the task with `id` to the end of `new_parent_id`'s children, or to the top level if `None`.
+    pub fn move_task(&mut self, id: &str, new_parent_id: Option<&str>) -> bool {
+        self.move_task_to(id, new_parent_id, None)
+    }
+
+    /// Moves the task with `id` to `index` among `new_parent_id`'s children, see `insert_task`.
+    /// Returns `false` and leaves the tree unchanged if either task is missing,
+    /// or if `new_parent_id` is the task itself or one of its descendants.
+    pub fn move_task_to(
+        &mut self,
+        id: &str,
+        new_parent_id: Option<&str>,
+        index: Option<usize>,
+    ) -> bool {
+        let Some(task) = self.find_task(id) else {
+            return false;
+        };
+        if let Some(parent_id) = new_parent_id {
+            if parent_id == id
+                || Self::find_task_recursive(task, parent_id).is_some()
+                || self.find_task(parent_id).is_none()
+            {
+                return false;
+            }
+        }
+        let Some(task) = self.remove_task(id) else {
+            return false;
+        };
+        self.insert_task(new_parent_id, index, task)
+    }
+
+    /// Moves the task with `id` one step up among its siblings. Returns `false` if it is already first.
+    pub fn move_task_up(&mut self, id: &str) -> bool {
+        match self.find_siblings_mut(id) {
+            Some((siblings, index)) if index > 0 => {
+                siblings.swap(index, index - 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves the task with `id` one step down among its siblings. Returns `false` if it is already last.
+    pub fn move_task_down(&mut self, id: &str) -> bool {
+        match self.find_siblings_mut(id) {
+            Some((siblings, index)) if index + 1 < siblings.len() => {
+                siblings.swap(index, index + 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Makes the task with `id` the last child of its previous sibling.
+    pub fn indent_task(&mut self, id: &str) -> bool {
+        let previous_id = match self.find_siblings_mut(id) {
+            Some((siblings, index)) if index > 0 => siblings[index - 1].id.clone(),
+            _ => return false,
+        };
+        self.move_task(id, Some(&previous_id))
+    }
+
+    /// Moves the task with `id` out of its parent, placing it right after the parent.
+    pub fn outdent_task(&mut self, id: &str) -> bool {
+        let Some(parent_id) = self.parent_id(id) else {
+            return false;
+        };
+        let Some(task) = self.remove_task(id) else {
+            return false;
+        };
+        match self.find_siblings_mut(&parent_id) {
+            Some((siblings, index)) => siblings.insert(index + 1, task),
+            None => self.tasks.push(task),
+        }
+        true
+    }
+
     /// Returns the next task ID in the flattened tasks tree.
     /// If `current_id` is provided, the next task in pre-order is returned (cycling back to the start).
     /// If `current_id` is None or not found, returns the first task ID if available.
@@ -159,7 +481,7 @@ impl EstimateApp {
     }
 
     /// Flattens the tasks tree into a pre-order vector of task references.
-    fn flatten_tasks(&self) -> Vec<&Task> {
+    pub fn flatten_tasks(&self) -> Vec<&Task> {
         let mut flat = Vec::new();
         for task in &self.tasks {
             Self::flatten_task_recursive(task, &mut flat);
@@ -175,3 +497,122 @@ impl EstimateApp {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_ids_no_task_uses() {
+        let mut app = EstimateApp::new();
+        app.get_tasks_mut()
+            .push(Task::new("task-0", "Imported", 0.0));
+        app.get_tasks_mut()
+            .push(Task::new("task-2", "Imported", 0.0));
+
+        let first = app.generate_task_id();
+        let design = app.add_task("Design");
+        let second = app.generate_task_id();
+        assert_eq!(first, "task-1");
+        assert_eq!(design, "task-3");
+        assert_eq!(second, "task-4");
+    }
+
+    #[test]
+    fn repairs_empty_and_duplicate_ids() {
+        let mut app = EstimateApp::new();
+        let mut parent = Task::new("task-0", "Parent", 0.0);
+        parent
+            .children
+            .push(Task::new("task-0", "Duplicate child", 0.0));
+        parent.children.push(Task::new("", "No ID", 0.0));
+        app.get_tasks_mut().push(parent);
+        app.get_tasks_mut()
+            .push(Task::new("task-1", "Sibling", 0.0));
+
+        assert_eq!(app.repair_task_ids(), 2);
+        let ids: Vec<&str> = app
+            .flatten_tasks()
+            .into_iter()
+            .map(|task| task.id.as_str())
+            .collect();
+        // The first occurrence keeps its ID, the repaired ones get unused IDs.
+        assert_eq!(ids, ["task-0", "task-2", "task-3", "task-1"]);
+        assert_eq!(app.repair_task_ids(), 0);
+    }
+
+    /// Names of the tasks in pre-order, with their depth as indentation.
+    fn outline(app: &EstimateApp) -> Vec<String> {
+        fn push(task: &Task, depth: usize, lines: &mut Vec<String>) {
+            lines.push(format!("{}{}", "  ".repeat(depth), task.name));
+            for child in &task.children {
+                push(child, depth + 1, lines);
+            }
+        }
+        let mut lines = Vec::new();
+        for task in app.get_tasks() {
+            push(task, 0, &mut lines);
+        }
+        lines
+    }
+
+    /// A, with children A1 and A2, followed by B.
+    fn app_with_tree() -> (EstimateApp, [String; 4]) {
+        let mut app = EstimateApp::new();
+        let a = app.add_task("A");
+        let a1 = app.add_child_task(&a, "A1", 0.0).unwrap();
+        let a2 = app.add_child_task(&a, "A2", 0.0).unwrap();
+        let b = app.add_task("B");
+        (app, [a, a1, a2, b])
+    }
+
+    #[test]
+    fn refuses_to_move_a_task_into_itself_or_its_descendants() {
+        let (mut app, [a, a1, _, b]) = app_with_tree();
+        let before = outline(&app);
+        assert!(!app.move_task_to(&a, Some(&a), None));
+        assert!(!app.move_task_to(&a, Some(&a1), None));
+        assert!(!app.move_task_to(&a, Some("missing"), None));
+        assert!(!app.move_task_to("missing", Some(&b), None));
+        assert_eq!(outline(&app), before);
+
+        assert!(app.move_task_to(&a1, Some(&b), Some(0)));
+        assert_eq!(outline(&app), ["A", "  A2", "B", "  A1"]);
+        assert!(app.move_task_to(&b, None, Some(0)));
+        assert_eq!(outline(&app), ["B", "  A1", "A", "  A2"]);
+    }
+
+    #[test]
+    fn reorders_only_within_the_siblings() {
+        let (mut app, [a, a1, a2, b]) = app_with_tree();
+        assert!(!app.move_task_up(&a));
+        assert!(!app.move_task_up(&a1));
+        assert!(!app.move_task_down(&a2));
+        assert!(!app.move_task_down(&b));
+
+        assert!(app.move_task_down(&a1));
+        assert!(app.move_task_up(&b));
+        assert_eq!(outline(&app), ["B", "A", "  A2", "  A1"]);
+    }
+
+    #[test]
+    fn indents_and_outdents_at_the_edges() {
+        let (mut app, [a, a1, a2, b]) = app_with_tree();
+        // The first task of a list has no previous sibling to move into.
+        assert!(!app.indent_task(&a));
+        assert!(!app.indent_task(&a1));
+        // Top-level tasks have no parent to move out of.
+        assert!(!app.outdent_task(&a));
+        assert!(!app.outdent_task("missing"));
+
+        assert!(app.indent_task(&a2));
+        assert_eq!(outline(&app), ["A", "  A1", "    A2", "B"]);
+        assert!(app.indent_task(&b));
+        assert_eq!(outline(&app), ["A", "  A1", "    A2", "  B"]);
+        // Outdenting places the task right after its former parent.
+        assert!(app.outdent_task(&a2));
+        assert_eq!(outline(&app), ["A", "  A1", "  A2", "  B"]);
+        assert!(app.outdent_task(&a1));
+        assert_eq!(outline(&app), ["A", "  A2", "  B", "A1"]);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{
+    csv, dot, freemind, import::Import, markdown, mermaid, opml, png, report, svg, EstimateApp,
+    EstimateUnits,
+};
+
+/// Formats that can be imported into a new document from the File menu.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+pub enum ImportFormat {
+    Markdown,
+    Freemind,
+    Opml,
+}
+
+impl ImportFormat {
+    pub const ALL: [ImportFormat; 3] = [
+        ImportFormat::Markdown,
+        ImportFormat::Freemind,
+        ImportFormat::Opml,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportFormat::Markdown => "Markdown outline",
+            ImportFormat::Freemind => "FreeMind map",
+            ImportFormat::Opml => "OPML outline",
+        }
+    }
+
+    /// Reads `text` into a new document. Plain numbers in the file are read in `units`.
+    /// Also returns a description of every part of the file that could not be interpreted.
+    pub fn import(
+        &self,
+        text: &str,
+        units: EstimateUnits,
+    ) -> Result<(EstimateApp, Vec<String>), String> {
+        let import = match self {
+            ImportFormat::Markdown => Import {
+                estimate_app: markdown::from_markdown(text),
+                issues: Vec::new(),
+            },
+            ImportFormat::Freemind => {
+                freemind::from_freemind(text, units).map_err(|error| error.to_string())?
+            }
+            ImportFormat::Opml => {
+                opml::from_opml(text, units).map_err(|error| error.to_string())?
+            }
+        };
+        let issues = import.issues.iter().map(ToString::to_string).collect();
+        Ok((import.estimate_app, issues))
+    }
+}
+
+/// Formats the current document can be exported to from the File menu.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+pub enum ExportFormat {
+    Markdown,
+    Opml,
+    Csv,
+    Svg,
+    Png,
+    Dot,
+    Mermaid,
+    Html,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 8] = [
+        ExportFormat::Markdown,
+        ExportFormat::Opml,
+        ExportFormat::Csv,
+        ExportFormat::Svg,
+        ExportFormat::Png,
+        ExportFormat::Dot,
+        ExportFormat::Mermaid,
+        ExportFormat::Html,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown outline",
+            ExportFormat::Opml => "OPML outline",
+            ExportFormat::Csv => "CSV spreadsheet",
+            ExportFormat::Svg => "SVG image",
+            ExportFormat::Png => "PNG image",
+            ExportFormat::Dot => "Graphviz DOT graph",
+            ExportFormat::Mermaid => "Mermaid mindmap",
+            ExportFormat::Html => "HTML report",
+        }
+    }
+
+    /// The format whose `extension` is `extension`, ignoring case.
+    pub fn from_extension(extension: &str) -> Option<ExportFormat> {
+        ExportFormat::ALL
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Appended to export paths typed without an extension.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Opml => "opml",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Svg => "svg",
+            ExportFormat::Png => "png",
+            ExportFormat::Dot => "dot",
+            ExportFormat::Mermaid => "mmd",
+            ExportFormat::Html => "html",
+        }
+    }
+
+    pub fn export(&self, app: &EstimateApp) -> Result<Vec<u8>, String> {
+        match self {
+            ExportFormat::Markdown => Ok(markdown::to_markdown(app).into_bytes()),
+            ExportFormat::Opml => Ok(opml::to_opml(app).into_bytes()),
+            ExportFormat::Csv => Ok(csv::to_csv(app).into_bytes()),
+            ExportFormat::Svg => Ok(svg::to_svg(app).into_bytes()),
+            ExportFormat::Png => {
+                png::to_png(app, png::DEFAULT_SCALE).map_err(|error| error.to_string())
+            }
+            ExportFormat::Dot => Ok(dot::to_dot(app).into_bytes()),
+            ExportFormat::Mermaid => Ok(mermaid::to_mermaid(app).into_bytes()),
+            ExportFormat::Html => Ok(report::to_html(app).into_bytes()),
+        }
+    }
+}
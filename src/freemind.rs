@@ -0,0 +1,168 @@
+//! Imports FreeMind and Freeplane `.mm` maps.
+//!
+//! The central node of the map stands for the whole document, so its children become the
+//! top-level tasks. Estimates are read from an `estimate` attribute,
+//! `<attribute NAME="estimate" VALUE="8h"/>`, or from a trailing `(8h)` in the node text
+//! as in the Markdown outline format.
+
+use roxmltree::Node;
+
+use crate::{
+    import::{task_path, Import, ImportIssue},
+    markdown, EstimateApp, EstimateUnits, Task, ThreePointEstimate,
+};
+
+/// Name of the node attribute holding the estimate, compared case-insensitively.
+const ESTIMATE_ATTRIBUTE: &str = "estimate";
+
+#[derive(Debug)]
+pub enum FreemindError {
+    Xml(roxmltree::Error),
+    /// The document is XML, but its root element is not `<map>`.
+    NotAMap,
+}
+
+impl std::fmt::Display for FreemindError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FreemindError::Xml(error) => write!(f, "Not a valid XML file: {}", error),
+            FreemindError::NotAMap => write!(f, "Not a FreeMind map, expected a <map> element"),
+        }
+    }
+}
+
+impl std::error::Error for FreemindError {}
+
+impl From<roxmltree::Error> for FreemindError {
+    fn from(error: roxmltree::Error) -> Self {
+        FreemindError::Xml(error)
+    }
+}
+
+/// Reads a `.mm` map into a new document using `units` for plain numbers.
+pub fn from_freemind(text: &str, units: EstimateUnits) -> Result<Import, FreemindError> {
+    let xml = roxmltree::Document::parse(text)?;
+    let map = xml.root_element();
+    if !map.has_tag_name("map") {
+        return Err(FreemindError::NotAMap);
+    }
+
+    let mut import = Import {
+        estimate_app: EstimateApp::new(),
+        issues: Vec::new(),
+    };
+    import.estimate_app.units = units;
+    for central in child_nodes(map) {
+        let (name, estimate) = read_node(central, "", &units, &mut import.issues);
+        if estimate.is_some() {
+            import.issues.push(ImportIssue {
+                path: name.clone(),
+                message: "the central node stands for the whole document, its estimate is ignored"
+                    .into(),
+            });
+        }
+        for node in child_nodes(central) {
+            import_node(node, None, "", &mut import);
+        }
+    }
+    Ok(import)
+}
+
+fn import_node(
+    node: Node<'_, '_>,
+    parent_id: Option<&str>,
+    parent_path: &str,
+    import: &mut Import,
+) {
+    let units = import.estimate_app.units;
+    let (name, estimate) = read_node(node, parent_path, &units, &mut import.issues);
+    let id = import.estimate_app.generate_task_id();
+    let mut task = Task::new(&id, &name, 0.0);
+    if let Some(estimate) = estimate {
+        task.set_three_point(estimate);
+    }
+    import.estimate_app.insert_task(parent_id, None, task);
+
+    let path = task_path(parent_path, &name);
+    for child in child_nodes(node) {
+        import_node(child, Some(&id), &path, import);
+    }
+}
+
+/// Returns the name and estimate of `node`, recording anything that could not be read.
+fn read_node(
+    node: Node<'_, '_>,
+    parent_path: &str,
+    units: &EstimateUnits,
+    issues: &mut Vec<ImportIssue>,
+) -> (String, Option<ThreePointEstimate>) {
+    let text = node_text(node);
+    let (name, text_estimate) = markdown::split_estimate(&text, units);
+    let mut name = name.to_owned();
+    if name.is_empty() {
+        name = "Untitled".to_owned();
+        issues.push(ImportIssue {
+            path: task_path(parent_path, &name),
+            message: match node.attribute("ID") {
+                Some(id) => format!("node {} has no text", id),
+                None => "node has no text".to_owned(),
+            },
+        });
+    }
+
+    let attribute = node.children().find(|child| {
+        child.has_tag_name("attribute")
+            && child
+                .attribute("NAME")
+                .is_some_and(|attribute| attribute.trim().eq_ignore_ascii_case(ESTIMATE_ATTRIBUTE))
+    });
+    let attribute_estimate =
+        match attribute.map(|attribute| attribute.attribute("VALUE").unwrap_or("")) {
+            Some(value) => match ThreePointEstimate::parse(value, units) {
+                Ok(estimate) => Some(estimate),
+                Err(error) => {
+                    issues.push(ImportIssue {
+                        path: task_path(parent_path, &name),
+                        message: format!(
+                            "estimate attribute \"{}\" is not an estimate: {}",
+                            value, error
+                        ),
+                    });
+                    None
+                }
+            },
+            None => None,
+        };
+    (name, attribute_estimate.or(text_estimate))
+}
+
+/// The plain text of a node, from its `TEXT` attribute or its HTML rich content.
+fn node_text(node: Node<'_, '_>) -> String {
+    if let Some(text) = node.attribute("TEXT") {
+        return text.trim().to_owned();
+    }
+    let rich_content = node.children().find(|child| {
+        child.has_tag_name("richcontent")
+            && child.attribute("TYPE").map_or(true, |kind| kind == "NODE")
+    });
+    let Some(rich_content) = rich_content else {
+        return String::new();
+    };
+    let text: Vec<&str> = rich_content
+        .descendants()
+        .filter(|node| node.is_text())
+        // Skip the stylesheet in `<head>`.
+        .filter(|node| {
+            !node
+                .ancestors()
+                .any(|ancestor| ancestor.has_tag_name("head"))
+        })
+        .filter_map(|node| node.text())
+        .flat_map(str::split_whitespace)
+        .collect();
+    text.join(" ")
+}
+
+fn child_nodes<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(|child| child.has_tag_name("node"))
+}
@@ -0,0 +1,38 @@
+//! What the importers have in common: the document they read and everything in the file
+//! they could not interpret, reported against the path of the task it belongs to.
+
+use crate::EstimateApp;
+
+/// Separates task names in a path such as `Phase / Build`.
+pub(crate) const PATH_SEPARATOR: &str = " / ";
+
+/// Something in the file an importer could not make sense of. The task is still imported
+/// as well as possible, e.g. without an estimate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportIssue {
+    /// Names of the task and its ancestors, joined by `PATH_SEPARATOR`.
+    pub path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// A document read from a file, with everything that could not be interpreted.
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub estimate_app: EstimateApp,
+    pub issues: Vec<ImportIssue>,
+}
+
+/// The path of a task called `name` below the task at `parent_path`, empty for the top level.
+pub(crate) fn task_path(parent_path: &str, name: &str) -> String {
+    if parent_path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}{}{}", parent_path, PATH_SEPARATOR, name)
+    }
+}
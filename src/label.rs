@@ -0,0 +1,31 @@
+//! Text shown next to task names on the canvas and in the exports.
+
+use crate::{EstimateUnits, Rollup, Task};
+
+/// Shows the single estimate, followed by the optimistic–pessimistic range if the task has one.
+pub(crate) fn estimate_label(task: &Task, units: &EstimateUnits) -> String {
+    match task.three_point {
+        Some(range) => format!(
+            "{} ({}–{})",
+            units.format(task.estimate),
+            units.format(range.optimistic),
+            units.format(range.pessimistic)
+        ),
+        None => units.format(task.estimate),
+    }
+}
+
+/// `estimate_label`, followed by the rolled-up estimate for tasks with children, e.g. `16h · Σ 28h`.
+pub(crate) fn estimate_summary(
+    task: &Task,
+    rollup: Option<&Rollup>,
+    units: &EstimateUnits,
+) -> String {
+    let label = estimate_label(task, units);
+    match rollup {
+        Some(rollup) if !task.children.is_empty() => {
+            format!("{} · Σ {}", label, units.format(rollup.total))
+        }
+        _ => label,
+    }
+}
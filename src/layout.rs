@@ -0,0 +1,812 @@
+//! Where the boxes of the mindmap go. Shared by the canvas and the image exports,
+//! so it only uses `emath` geometry and builds without egui.
+
+use std::collections::HashMap;
+use std::f32::consts::{PI, TAU};
+
+use emath::{Pos2, Rect, Vec2};
+
+use crate::label::estimate_label;
+use crate::scene::FONT_SIZE;
+use crate::text::{ellipsize, line_height, text_width, wrap};
+use crate::{EstimateApp, EstimateUnits, Rollup, Task};
+
+pub const RADII: Vec2 = Vec2::new(75.0, 25.0);
+/// Size of the screen the mindmap is laid out on when exporting it as an image.
+pub const EXPORT_CANVAS_SIZE: Vec2 = Vec2::new(1280.0, 800.0);
+
+/// End points of the line connecting a parent box to a child box, on the edges of both boxes.
+pub(crate) fn connector(from_rect: Rect, to_rect: Rect) -> [Pos2; 2] {
+    let dir = (to_rect.center() - from_rect.center()).normalized();
+    [edge_point(from_rect, dir), edge_point(to_rect, -dir)]
+}
+
+/// Where a ray from the center of `rect` in direction `dir` leaves the box.
+fn edge_point(rect: Rect, dir: Vec2) -> Pos2 {
+    let half = rect.size() / 2.0;
+    let scale = |half: f32, dir: f32| {
+        if dir.abs() < f32::EPSILON {
+            f32::INFINITY
+        } else {
+            half / dir.abs()
+        }
+    };
+    rect.center() + dir * scale(half.x, dir.x).min(scale(half.y, dir.y))
+}
+
+/// Center of the bubble showing the rolled-up estimate of a parent, on its top-left corner.
+pub(crate) fn sum_position(parent_rect: Rect) -> Pos2 {
+    parent_rect.left_top()
+}
+
+/// Centers of `count` lines of text `line_height` apart, stacked in the middle of `rect`.
+pub(crate) fn line_centers(
+    rect: Rect,
+    count: usize,
+    line_height: f32,
+) -> impl Iterator<Item = Pos2> {
+    let top = rect.center().y - (count as f32 - 1.0) * line_height / 2.0;
+    (0..count).map(move |index| Pos2::new(rect.center().x, top + index as f32 * line_height))
+}
+
+/// How the boxes of a document are arranged, chosen per document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum LayoutStyle {
+    /// Every level on its own ring around the root.
+    #[default]
+    Radial,
+    /// The root on the left, every level a column further to the right.
+    Tree,
+    /// Like `Tree`, with the top-level tasks split between both sides of the root.
+    Mindmap,
+    /// The root at the top, every level a row further down.
+    OrgChart,
+}
+
+impl LayoutStyle {
+    pub const ALL: [LayoutStyle; 4] = [
+        LayoutStyle::Radial,
+        LayoutStyle::Tree,
+        LayoutStyle::Mindmap,
+        LayoutStyle::OrgChart,
+    ];
+
+    pub(crate) fn layout(self) -> &'static dyn Layout {
+        match self {
+            LayoutStyle::Radial => &RadialLayout,
+            LayoutStyle::Tree => &TreeLayout,
+            LayoutStyle::Mindmap => &MindmapLayout,
+            LayoutStyle::OrgChart => &OrgChartLayout,
+        }
+    }
+}
+
+impl std::fmt::Display for LayoutStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LayoutStyle::Radial => "Radial",
+            LayoutStyle::Tree => "Tree",
+            LayoutStyle::Mindmap => "Mindmap",
+            LayoutStyle::OrgChart => "Org chart",
+        })
+    }
+}
+
+/// A task with the box a `Layout` gives it.
+pub(crate) struct PlacedTask<'a> {
+    pub task: &'a Task,
+    pub rect: Rect,
+    /// The connector from the parent's box (or the root) to this one, as a polyline.
+    pub edge: Vec<Pos2>,
+    pub depth_level: usize,
+}
+
+/// Turns the task tree into boxes, used for painting, hit-testing and the image exports alike.
+pub(crate) trait Layout {
+    /// Boxes for `tasks` and all their descendants in pre-order, around `root_rect` in the
+    /// middle of `canvas`. `node_size` is the size each task's box needs for its text.
+    fn place<'a>(
+        &self,
+        tasks: &'a [Task],
+        root_rect: Rect,
+        canvas: Rect,
+        node_size: &dyn Fn(&Task) -> Vec2,
+    ) -> Vec<PlacedTask<'a>>;
+}
+
+/// Space between the text of a node and the edge of its box.
+const NODE_PADDING: Vec2 = Vec2::new(10.0, 4.0);
+/// Lines of a task name shown before it is cut off.
+const MAX_NAME_LINES: usize = 3;
+
+/// One task's box in a `LayoutResult`, with the text painted in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutNode {
+    pub task_id: String,
+    /// The task's name, wrapped to fit the box.
+    pub name_lines: Vec<String>,
+    /// The task's own estimate, from `estimate_label`.
+    pub estimate: String,
+    pub rect: Rect,
+    pub depth_level: usize,
+    pub has_children: bool,
+}
+
+impl LayoutNode {
+    /// Every line of text in the box, top to bottom.
+    pub fn lines(&self) -> Vec<&str> {
+        self.name_lines
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(self.estimate.as_str()))
+            .collect()
+    }
+}
+
+/// Everything painting and hit-testing need to know about where things are, in mindmap
+/// coordinates with the root box centered on the origin. Computed by `compute_layout`
+/// without egui, so it can be cached between frames.
+#[derive(Debug, Clone)]
+pub struct LayoutResult {
+    /// Size of the canvas the layout was computed for.
+    pub canvas_size: Vec2,
+    pub root_rect: Rect,
+    /// The rolled-up total shown in the root box.
+    pub root_estimate: String,
+    /// Every task in pre-order.
+    pub nodes: Vec<LayoutNode>,
+    /// The connector from every task to its parent, as a polyline.
+    pub edges: Vec<Vec<Pos2>>,
+    pub rollups: HashMap<String, Rollup>,
+    /// Encloses the root and every node.
+    pub bounds: Rect,
+}
+
+impl LayoutResult {
+    /// The node whose box contains `position`, the one painted on top if boxes overlap.
+    pub fn node_at(&self, position: Pos2) -> Option<&LayoutNode> {
+        self.nodes
+            .iter()
+            .rev()
+            .find(|node| node.rect.contains(position))
+    }
+
+    pub fn node(&self, task_id: &str) -> Option<&LayoutNode> {
+        self.nodes.iter().find(|node| node.task_id == task_id)
+    }
+}
+
+/// The text of a task's box and the size it needs.
+struct NodeText {
+    name_lines: Vec<String>,
+    estimate: String,
+    size: Vec2,
+}
+
+/// Wraps the name of `task` to `max_width` and sizes the box to fit, never smaller than `RADII`.
+fn node_text(task: &Task, units: &EstimateUnits, max_width: f32) -> NodeText {
+    let min_width = (RADII.x * 2.0).min(max_width);
+    let available = max_width - NODE_PADDING.x * 2.0;
+    let name_lines = wrap(&task.name, available, MAX_NAME_LINES, FONT_SIZE);
+    let estimate = ellipsize(&estimate_label(task, units), available, FONT_SIZE);
+    let widest = name_lines
+        .iter()
+        .chain(std::iter::once(&estimate))
+        .map(|line| text_width(line, FONT_SIZE))
+        .fold(0.0, f32::max);
+    let height = (name_lines.len() + 1) as f32 * line_height(FONT_SIZE);
+    let size = Vec2::new(
+        (widest + NODE_PADDING.x * 2.0).clamp(min_width, max_width),
+        (height + NODE_PADDING.y * 2.0).max(RADII.y * 2.0),
+    );
+    NodeText {
+        name_lines,
+        estimate,
+        size,
+    }
+}
+
+/// Lays out `app` with its layout style on a canvas of `canvas_size`.
+pub fn compute_layout(app: &EstimateApp, canvas_size: Vec2) -> LayoutResult {
+    let root_rect = Rect::from_center_size(Pos2::ZERO, RADII * 2.0);
+    let canvas = Rect::from_center_size(Pos2::ZERO, canvas_size);
+    let max_width = app.max_node_width.max(RADII.x);
+    let mut texts: HashMap<&str, NodeText> = app
+        .flatten_tasks()
+        .into_iter()
+        .map(|task| (task.id.as_str(), node_text(task, &app.units, max_width)))
+        .collect();
+    let node_size = |task: &Task| {
+        texts
+            .get(task.id.as_str())
+            .map_or(RADII * 2.0, |text| text.size)
+    };
+    let placed = app
+        .layout_style
+        .layout()
+        .place(app.get_tasks(), root_rect, canvas, &node_size);
+    let edges = placed
+        .iter()
+        .map(|placed_task| placed_task.edge.clone())
+        .collect();
+    let nodes: Vec<LayoutNode> = placed
+        .iter()
+        .map(|placed_task| {
+            let task = placed_task.task;
+            let text = texts
+                .remove(task.id.as_str())
+                .unwrap_or_else(|| node_text(task, &app.units, max_width));
+            LayoutNode {
+                task_id: task.id.clone(),
+                name_lines: text.name_lines,
+                estimate: text.estimate,
+                rect: placed_task.rect,
+                depth_level: placed_task.depth_level,
+                has_children: !task.children.is_empty(),
+            }
+        })
+        .collect();
+    LayoutResult {
+        canvas_size,
+        root_rect,
+        root_estimate: app.units.format(app.total()),
+        edges,
+        bounds: nodes
+            .iter()
+            .fold(root_rect, |bounds, node| bounds.union(node.rect)),
+        nodes,
+        rollups: app.rollups(),
+    }
+}
+
+/// `cached` if it was computed for a canvas of `canvas_size`, otherwise a new layout of `app`.
+/// Callers drop the cache whenever the document changes.
+pub fn cached_layout(
+    cached: Option<LayoutResult>,
+    app: &EstimateApp,
+    canvas_size: Vec2,
+) -> LayoutResult {
+    match cached {
+        Some(layout) if layout.canvas_size == canvas_size => layout,
+        _ => compute_layout(app, canvas_size),
+    }
+}
+
+/// Radius of the circle the top-level tasks are placed on, for a canvas of the given size.
+pub fn layout_radius(canvas: Rect) -> f32 {
+    canvas.width().min(canvas.height()) * 0.3
+}
+
+/// Space kept between any two boxes of the radial layout.
+const RADIAL_GAP: f32 = 20.0;
+
+/// Places every level of the tree on its own ring around the root. Each subtree gets a wedge
+/// of its parent's wedge in proportion to its number of leaves and stays inside it, so branches
+/// never cross, and every ring is made large enough that no two boxes overlap.
+pub(crate) struct RadialLayout;
+
+/// A task and its direction from the root, found by the first pass of `RadialLayout`.
+struct Spoke<'a> {
+    task: &'a Task,
+    size: Vec2,
+    depth_level: usize,
+    angle: f32,
+}
+
+impl Layout for RadialLayout {
+    fn place<'a>(
+        &self,
+        tasks: &'a [Task],
+        root_rect: Rect,
+        canvas: Rect,
+        node_size: &dyn Fn(&Task) -> Vec2,
+    ) -> Vec<PlacedTask<'a>> {
+        let Some(first) = tasks.first() else {
+            return Vec::new();
+        };
+        let total: usize = tasks.iter().map(leaf_count).sum();
+        let mut spokes = Vec::new();
+        // The first task points to the right, like the old layout did.
+        let mut start = -TAU * leaf_count(first) as f32 / total as f32 / 2.0;
+        for task in tasks {
+            let wedge = TAU * leaf_count(task) as f32 / total as f32;
+            assign_angles(task, 0, start, wedge, node_size, &mut spokes);
+            start += wedge;
+        }
+
+        // Boxes can't overlap when their centers are further apart than half of both
+        // diagonals, whatever their direction. Every ring keeps that distance from its
+        // neighbours on the ring and from the ring inside it.
+        let depth = spokes
+            .iter()
+            .map(|spoke| spoke.depth_level + 1)
+            .max()
+            .unwrap_or(0);
+        let mut diagonals = vec![0.0_f32; depth];
+        for spoke in &spokes {
+            let diagonal = &mut diagonals[spoke.depth_level];
+            *diagonal = diagonal.max(spoke.size.length());
+        }
+        let mut ring_radii = Vec::with_capacity(depth);
+        let mut inner_diagonal = root_rect.size().length();
+        let mut radius = layout_radius(canvas);
+        for (depth_level, &diagonal) in diagonals.iter().enumerate() {
+            let inner_radius = ring_radii.last().copied().unwrap_or(0.0);
+            radius = radius.max(inner_radius + (inner_diagonal + diagonal) / 2.0 + RADIAL_GAP);
+            let mut angles: Vec<f32> = spokes
+                .iter()
+                .filter(|spoke| spoke.depth_level == depth_level)
+                .map(|spoke| spoke.angle)
+                .collect();
+            if angles.len() > 1 {
+                angles.sort_by(f32::total_cmp);
+                let closest = angles
+                    .windows(2)
+                    .map(|pair| pair[1] - pair[0])
+                    .fold(angles[0] + TAU - angles[angles.len() - 1], f32::min)
+                    .min(PI);
+                // Neighbours on the ring are a chord of 2 r sin(angle / 2) apart.
+                radius = radius.max((diagonal + RADIAL_GAP) / (2.0 * (closest / 2.0).sin()));
+            }
+            ring_radii.push(radius);
+            inner_diagonal = diagonal;
+        }
+
+        let center = root_rect.center();
+        let mut parent_rects: Vec<Rect> = Vec::with_capacity(depth);
+        spokes
+            .into_iter()
+            .map(|spoke| {
+                let radius = ring_radii[spoke.depth_level];
+                let position = center + Vec2::angled(spoke.angle) * radius;
+                let rect = Rect::from_center_size(position, spoke.size);
+                parent_rects.truncate(spoke.depth_level);
+                let parent_rect = parent_rects.last().copied().unwrap_or(root_rect);
+                parent_rects.push(rect);
+                PlacedTask {
+                    task: spoke.task,
+                    rect,
+                    edge: connector(parent_rect, rect).to_vec(),
+                    depth_level: spoke.depth_level,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Points `task` at the middle of its wedge and splits the wedge between its children.
+/// Children share at most half a circle, so a branch can't wrap around its own parent.
+fn assign_angles<'a>(
+    task: &'a Task,
+    depth_level: usize,
+    start: f32,
+    wedge: f32,
+    node_size: &dyn Fn(&Task) -> Vec2,
+    spokes: &mut Vec<Spoke<'a>>,
+) {
+    spokes.push(Spoke {
+        task,
+        size: node_size(task),
+        depth_level,
+        angle: start + wedge / 2.0,
+    });
+    let span = wedge.min(PI);
+    let per_leaf = span / leaf_count(task) as f32;
+    let mut start = start + (wedge - span) / 2.0;
+    for child in &task.children {
+        let wedge = per_leaf * leaf_count(child) as f32;
+        assign_angles(child, depth_level + 1, start, wedge, node_size, spokes);
+        start += wedge;
+    }
+}
+
+/// Space between neighbouring boxes of the same level in the tree layouts.
+const SIBLING_GAP: f32 = 20.0;
+/// Space between the boxes of one level and the next in the tree layouts.
+const LEVEL_GAP: f32 = 80.0;
+
+pub(crate) struct TreeLayout;
+
+impl Layout for TreeLayout {
+    fn place<'a>(
+        &self,
+        tasks: &'a [Task],
+        root_rect: Rect,
+        _canvas: Rect,
+        node_size: &dyn Fn(&Task) -> Vec2,
+    ) -> Vec<PlacedTask<'a>> {
+        let mut placed = Vec::new();
+        TidyTree::new(tasks, root_rect, Vec2::X, node_size).place(tasks, &mut placed);
+        placed
+    }
+}
+
+pub(crate) struct MindmapLayout;
+
+impl Layout for MindmapLayout {
+    fn place<'a>(
+        &self,
+        tasks: &'a [Task],
+        root_rect: Rect,
+        _canvas: Rect,
+        node_size: &dyn Fn(&Task) -> Vec2,
+    ) -> Vec<PlacedTask<'a>> {
+        // The first tasks go to the right and the rest to the left,
+        // split where both sides get the closest number of leaves.
+        let total: usize = tasks.iter().map(leaf_count).sum();
+        let mut right_leaves = 0;
+        let mut split = 0;
+        let mut best = usize::MAX;
+        for (index, task) in tasks.iter().enumerate() {
+            right_leaves += leaf_count(task);
+            let difference = (right_leaves * 2).abs_diff(total);
+            if difference < best {
+                best = difference;
+                split = index + 1;
+            }
+        }
+        let (right, left) = tasks.split_at(split);
+        let mut placed = Vec::new();
+        TidyTree::new(right, root_rect, Vec2::X, node_size).place(right, &mut placed);
+        TidyTree::new(left, root_rect, -Vec2::X, node_size).place(left, &mut placed);
+        placed
+    }
+}
+
+pub(crate) struct OrgChartLayout;
+
+impl Layout for OrgChartLayout {
+    fn place<'a>(
+        &self,
+        tasks: &'a [Task],
+        root_rect: Rect,
+        _canvas: Rect,
+        node_size: &dyn Fn(&Task) -> Vec2,
+    ) -> Vec<PlacedTask<'a>> {
+        let mut placed = Vec::new();
+        TidyTree::new(tasks, root_rect, Vec2::Y, node_size).place(tasks, &mut placed);
+        placed
+    }
+}
+
+/// Lays out a tree one level further from the root per depth, in `direction`. Every subtree
+/// gets a band across that direction wide enough for its own box and for all of its
+/// children's bands, so no two boxes overlap.
+///
+/// Connectors leave the parent's box on the side facing the next level, run straight to the
+/// end of the parent's level, cross the gap between the levels and enter the child's box on
+/// the side facing the parent. Only the gap is crossed at an angle, and no box reaches into
+/// it, so connectors never run through a box; the bands keep them from crossing each other.
+struct TidyTree<'s> {
+    root_rect: Rect,
+    /// From the root towards the deeper levels, `Vec2::X`, `-Vec2::X` or `Vec2::Y`.
+    direction: Vec2,
+    /// The direction siblings are placed in, downwards or to the right.
+    across: Vec2,
+    /// Distance from the center of the root to the center of every level.
+    level_offsets: Vec<f32>,
+    /// How deep every level is, the depth of its deepest box.
+    level_depths: Vec<f32>,
+    node_size: &'s dyn Fn(&Task) -> Vec2,
+}
+
+impl<'s> TidyTree<'s> {
+    fn new(
+        tasks: &[Task],
+        root_rect: Rect,
+        direction: Vec2,
+        node_size: &'s dyn Fn(&Task) -> Vec2,
+    ) -> Self {
+        let mut tree = Self {
+            root_rect,
+            direction,
+            across: Vec2::new(direction.y.abs(), direction.x.abs()),
+            level_offsets: Vec::new(),
+            level_depths: Vec::new(),
+            node_size,
+        };
+        // Every level is as deep as its deepest box.
+        let mut depths = Vec::new();
+        tree.collect_depths(tasks, 0, &mut depths);
+        let mut offset = tree.along(root_rect.size()) / 2.0;
+        for &depth in &depths {
+            tree.level_offsets.push(offset + LEVEL_GAP + depth / 2.0);
+            offset += LEVEL_GAP + depth;
+        }
+        tree.level_depths = depths;
+        tree
+    }
+
+    /// The point `along` from the center of the root towards the deeper levels and `across`
+    /// from it in the direction siblings are placed in.
+    fn point(&self, along: f32, across: f32) -> Pos2 {
+        self.root_rect.center() + self.direction * along + self.across * across
+    }
+
+    /// Distance from the center of the root to the far end of the level above `depth_level`,
+    /// or to the far edge of the root for the top level.
+    fn parent_level_end(&self, depth_level: usize) -> f32 {
+        match depth_level.checked_sub(1) {
+            Some(parent_level) => {
+                self.level_offsets[parent_level] + self.level_depths[parent_level] / 2.0
+            }
+            None => self.along(self.root_rect.size()) / 2.0,
+        }
+    }
+
+    /// The connector from `parent_rect` to `rect` on `depth_level`.
+    fn edge(&self, parent_rect: Rect, rect: Rect, depth_level: usize) -> Vec<Pos2> {
+        let offset = |rect: Rect| rect.center() - self.root_rect.center();
+        let parent_across = offset(parent_rect).dot(self.across);
+        let across = offset(rect).dot(self.across);
+        let level_start = self.level_offsets[depth_level] - self.level_depths[depth_level] / 2.0;
+        let mut points = vec![
+            self.point(
+                offset(parent_rect).dot(self.direction) + self.along(parent_rect.size()) / 2.0,
+                parent_across,
+            ),
+            self.point(self.parent_level_end(depth_level), parent_across),
+            self.point(level_start, across),
+            self.point(
+                offset(rect).dot(self.direction) - self.along(rect.size()) / 2.0,
+                across,
+            ),
+        ];
+        // The straight parts vanish next to the deepest box of a level.
+        points.dedup();
+        points
+    }
+
+    fn along(&self, size: Vec2) -> f32 {
+        (size * self.direction).length()
+    }
+
+    fn collect_depths(&self, tasks: &[Task], depth_level: usize, depths: &mut Vec<f32>) {
+        for task in tasks {
+            let depth = self.along((self.node_size)(task));
+            match depths.get_mut(depth_level) {
+                Some(level) => *level = level.max(depth),
+                None => depths.push(depth),
+            }
+            self.collect_depths(&task.children, depth_level + 1, depths);
+        }
+    }
+
+    /// Width of the band the subtree of `task` needs across the levels.
+    fn band(&self, task: &Task) -> f32 {
+        let own = ((self.node_size)(task) * self.across).length() + SIBLING_GAP;
+        let children: f32 = task.children.iter().map(|child| self.band(child)).sum();
+        own.max(children)
+    }
+
+    fn place<'a>(&self, tasks: &'a [Task], placed: &mut Vec<PlacedTask<'a>>) {
+        let total: f32 = tasks.iter().map(|task| self.band(task)).sum();
+        self.place_level(tasks, self.root_rect, 0, -total / 2.0, placed);
+    }
+
+    fn place_level<'a>(
+        &self,
+        tasks: &'a [Task],
+        parent_rect: Rect,
+        depth_level: usize,
+        mut start: f32,
+        placed: &mut Vec<PlacedTask<'a>>,
+    ) {
+        for task in tasks {
+            let band = self.band(task);
+            let center = self.root_rect.center()
+                + self.direction * self.level_offsets[depth_level]
+                + self.across * (start + band / 2.0);
+            let rect = Rect::from_center_size(center, (self.node_size)(task));
+            placed.push(PlacedTask {
+                task,
+                rect,
+                edge: self.edge(parent_rect, rect, depth_level),
+                depth_level,
+            });
+            let children: f32 = task.children.iter().map(|child| self.band(child)).sum();
+            self.place_level(
+                &task.children,
+                rect,
+                depth_level + 1,
+                start + (band - children) / 2.0,
+                placed,
+            );
+            start += band;
+        }
+    }
+}
+
+fn leaf_count(task: &Task) -> usize {
+    if task.children.is_empty() {
+        1
+    } else {
+        task.children.iter().map(leaf_count).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A lopsided tree with names from one word to several lines long, so boxes differ in
+    /// size within every level.
+    fn uneven_app() -> EstimateApp {
+        const WORDS: [&str; 6] = ["Design", "Build", "the", "import", "pipeline", "review"];
+        let mut app = EstimateApp::new();
+        let mut seed = 7_u32;
+        let mut next = |modulus: u32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) % modulus
+        };
+        let name = |next: &mut dyn FnMut(u32) -> u32| {
+            (0..=next(9))
+                .map(|_| WORDS[next(WORDS.len() as u32) as usize])
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let mut parents = Vec::new();
+        for _ in 0..5 {
+            let task_name = name(&mut next);
+            parents.push(app.add_task(&task_name));
+        }
+        for _ in 0..40 {
+            let parent = parents[next(parents.len() as u32) as usize].clone();
+            let task_name = name(&mut next);
+            let child = app
+                .add_child_task(&parent, &task_name, next(40) as f32)
+                .unwrap();
+            parents.push(child);
+        }
+        app
+    }
+
+    /// Whether the segment from `a` to `b` runs through the inside of `rect`.
+    fn crosses_rect(a: Pos2, b: Pos2, rect: Rect) -> bool {
+        let rect = rect.shrink(0.5);
+        let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+        let delta = b - a;
+        for (p, q) in [
+            (-delta.x, a.x - rect.min.x),
+            (delta.x, rect.max.x - a.x),
+            (-delta.y, a.y - rect.min.y),
+            (delta.y, rect.max.y - a.y),
+        ] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return false;
+                }
+            } else if p < 0.0 {
+                enter = enter.max(q / p);
+            } else {
+                exit = exit.min(q / p);
+            }
+        }
+        enter < exit
+    }
+
+    /// Whether two segments cross at a point inside both of them.
+    fn segments_cross(a: [Pos2; 2], b: [Pos2; 2]) -> bool {
+        let side = |p: Pos2, q: Pos2, r: Pos2| {
+            let cross = (q - p).x * (r - p).y - (q - p).y * (r - p).x;
+            if cross.abs() < 1e-3 {
+                0.0
+            } else {
+                cross.signum()
+            }
+        };
+        side(a[0], a[1], b[0]) * side(a[0], a[1], b[1]) < 0.0
+            && side(b[0], b[1], a[0]) * side(b[0], b[1], a[1]) < 0.0
+    }
+
+    #[test]
+    fn lays_out_every_task_in_pre_order() {
+        let app = uneven_app();
+        for style in LayoutStyle::ALL {
+            let mut app = app.clone();
+            app.layout_style = style;
+            let layout = compute_layout(&app, EXPORT_CANVAS_SIZE);
+            let ids: Vec<&str> = layout
+                .nodes
+                .iter()
+                .map(|node| node.task_id.as_str())
+                .collect();
+            let expected: Vec<&str> = app
+                .flatten_tasks()
+                .into_iter()
+                .map(|task| task.id.as_str())
+                .collect();
+            assert_eq!(ids, expected, "{style}");
+            assert_eq!(layout.edges.len(), layout.nodes.len(), "{style}");
+        }
+    }
+
+    #[test]
+    fn bounds_contain_every_node() {
+        let app = uneven_app();
+        for style in LayoutStyle::ALL {
+            let mut app = app.clone();
+            app.layout_style = style;
+            let layout = compute_layout(&app, EXPORT_CANVAS_SIZE);
+            assert!(layout.bounds.contains_rect(layout.root_rect), "{style}");
+            for node in &layout.nodes {
+                assert!(layout.bounds.contains_rect(node.rect), "{style}");
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_node_under_a_position() {
+        let layout = compute_layout(&EstimateApp::new_example_app(), EXPORT_CANVAS_SIZE);
+        for node in &layout.nodes {
+            let hit = layout.node_at(node.rect.center()).map(|hit| &hit.task_id);
+            assert_eq!(hit, Some(&node.task_id));
+            let hit = layout.node_at(node.rect.left_top()).map(|hit| &hit.task_id);
+            assert_eq!(hit, Some(&node.task_id));
+        }
+        assert!(layout.node_at(layout.root_rect.center()).is_none());
+        assert!(layout
+            .node_at(layout.bounds.max + Vec2::splat(1.0))
+            .is_none());
+    }
+
+    #[test]
+    fn reuses_the_cached_layout_for_the_same_canvas_size() {
+        let mut app = EstimateApp::new_example_app();
+        let cached = compute_layout(&app, EXPORT_CANVAS_SIZE);
+        app.add_task("Added later");
+
+        let same_size = cached_layout(Some(cached.clone()), &app, EXPORT_CANVAS_SIZE);
+        assert_eq!(same_size.nodes, cached.nodes);
+
+        let resized = cached_layout(Some(cached.clone()), &app, EXPORT_CANVAS_SIZE * 0.5);
+        assert_eq!(resized.canvas_size, EXPORT_CANVAS_SIZE * 0.5);
+        assert_eq!(resized.nodes.len(), cached.nodes.len() + 1);
+
+        let uncached = cached_layout(None, &app, EXPORT_CANVAS_SIZE);
+        assert_eq!(uncached.nodes.len(), cached.nodes.len() + 1);
+    }
+
+    #[test]
+    fn boxes_and_connectors_do_not_overlap() {
+        for mut app in [EstimateApp::new_example_app(), uneven_app()] {
+            for style in LayoutStyle::ALL {
+                app.layout_style = style;
+                let layout = compute_layout(&app, EXPORT_CANVAS_SIZE);
+                let boxes: Vec<Rect> = std::iter::once(layout.root_rect)
+                    .chain(layout.nodes.iter().map(|node| node.rect))
+                    .collect();
+                let segments: Vec<[Pos2; 2]> = layout
+                    .edges
+                    .iter()
+                    .flat_map(|edge| edge.windows(2).map(|pair| [pair[0], pair[1]]))
+                    .collect();
+
+                for (index, a) in boxes.iter().enumerate() {
+                    for b in &boxes[index + 1..] {
+                        assert!(
+                            !a.shrink(0.5).intersects(b.shrink(0.5)),
+                            "{style}: boxes {a:?} and {b:?} overlap"
+                        );
+                    }
+                }
+                for segment in &segments {
+                    for rect in &boxes {
+                        assert!(
+                            !crosses_rect(segment[0], segment[1], *rect),
+                            "{style}: connector {segment:?} runs through {rect:?}"
+                        );
+                    }
+                }
+                for (index, a) in segments.iter().enumerate() {
+                    for b in &segments[index + 1..] {
+                        assert!(
+                            !segments_cross(*a, *b),
+                            "{style}: connectors {a:?} and {b:?} cross"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,49 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+#[cfg(feature = "gui")]
 mod app;
+mod command;
+pub mod csv;
+pub mod document;
+pub mod dot;
+mod duration;
 mod estimate_app;
+mod file_format;
+pub mod freemind;
+pub mod import;
+mod label;
+mod layout;
+pub mod markdown;
+pub mod mermaid;
+pub mod opml;
+mod pert;
+pub mod png;
+pub mod report;
+mod rollup;
+mod scene;
+mod simulation;
+#[cfg(feature = "gui")]
+mod simulation_panel;
+pub mod svg;
 mod task;
+#[cfg(feature = "gui")]
 mod task_drawer;
+mod text;
+mod validation;
+mod view;
+mod xml;
+#[cfg(feature = "gui")]
 pub use app::TemplateApp;
+pub use command::{Command, History, HISTORY_LIMIT};
+pub use duration::{Duration, DurationParseError, EstimateUnit, EstimateUnits};
 pub use estimate_app::EstimateApp;
+pub use file_format::{ExportFormat, ImportFormat};
+pub use layout::{cached_layout, compute_layout, LayoutNode, LayoutResult, LayoutStyle};
+pub use pert::{PertSummary, RangeParseError, ThreePointEstimate};
+pub use rollup::{Rollup, RollupMode, RollupSource};
+pub use simulation::{
+    simulate, HistogramBin, SimulationInputs, SimulationResult, DEFAULT_ITERATIONS,
+};
 pub use task::Task;
+pub use validation::{validate, ValidationIssue};
+pub use view::View;
@@ -0,0 +1,224 @@
+//! Converts between `EstimateApp` and nested Markdown bullet lists such as
+//!
+//! ```text
+//! - Login page (8h)
+//!   - Form (4h)
+//!   - Validation (2h/4h/8h)
+//! ```
+//!
+//! Nesting maps onto `Task::children`, the trailing parentheses hold the estimate
+//! in the syntax of `ThreePointEstimate::parse`. Exports start with a comment such as
+//! `<!-- estimate units: d, 8h/day -->`, so the numbers mean the same when read back.
+
+use crate::{EstimateApp, EstimateUnit, EstimateUnits, Task, ThreePointEstimate};
+
+const INDENT: &str = "  ";
+
+const UNITS_PREFIX: &str = "<!-- estimate units:";
+const UNITS_SUFFIX: &str = "-->";
+
+/// Writes every task as a bullet, indented two spaces per level, after a comment with the units.
+/// Tasks without an estimate get a zero annotation, so a name that ends in parentheses
+/// isn't read back as an estimate.
+pub fn to_markdown(app: &EstimateApp) -> String {
+    let mut markdown = format!(
+        "{} {}, {}h/day {}\n",
+        UNITS_PREFIX,
+        app.units.unit.suffix(),
+        app.units.hours_per_day,
+        UNITS_SUFFIX
+    );
+    for task in app.get_tasks() {
+        write_task(task, 0, &app.units, &mut markdown);
+    }
+    markdown
+}
+
+fn write_task(task: &Task, depth: usize, units: &EstimateUnits, markdown: &mut String) {
+    markdown.push_str(&INDENT.repeat(depth));
+    markdown.push_str("- ");
+    markdown.push_str(&task.name);
+    markdown.push_str(&format!(" ({})", estimate_annotation(task, units)));
+    markdown.push('\n');
+    for child in &task.children {
+        write_task(child, depth + 1, units, markdown);
+    }
+}
+
+/// The estimate as written inside the trailing parentheses, without rounding.
+fn estimate_annotation(task: &Task, units: &EstimateUnits) -> String {
+    match task.three_point {
+        Some(range) => range.format_exact(units),
+        None => units.format_exact(task.estimate),
+    }
+}
+
+/// Reads a nested bullet list into a new document.
+/// Estimates are read in the units of the comment `to_markdown` writes, or in the default
+/// units if there is none, never in those of the open document.
+/// Lines that are not list items, such as headings and paragraphs, are skipped.
+/// A trailing `(...)` that does not parse as an estimate is kept as part of the name.
+pub fn from_markdown(text: &str) -> EstimateApp {
+    let units = text
+        .lines()
+        .find_map(parse_units_comment)
+        .unwrap_or_default();
+    let mut app = EstimateApp::new();
+    app.units = units;
+
+    // Indentation and ID of the most recent item on each open level.
+    let mut open: Vec<(usize, String)> = Vec::new();
+    for line in text.lines() {
+        let Some((indent, item)) = parse_list_item(line) else {
+            continue;
+        };
+        while open
+            .last()
+            .is_some_and(|(open_indent, _)| *open_indent >= indent)
+        {
+            open.pop();
+        }
+
+        let (name, estimate) = split_estimate(item, &units);
+        let id = app.generate_task_id();
+        let mut task = Task::new(&id, name, 0.0);
+        if let Some(estimate) = estimate {
+            task.set_three_point(estimate);
+        }
+        let parent_id = open.last().map(|(_, id)| id.as_str());
+        app.insert_task(parent_id, None, task);
+        open.push((indent, id));
+    }
+    app
+}
+
+/// Reads `<!-- estimate units: d, 8h/day -->`, `None` if `line` is something else.
+fn parse_units_comment(line: &str) -> Option<EstimateUnits> {
+    let content = line
+        .trim()
+        .strip_prefix(UNITS_PREFIX)?
+        .strip_suffix(UNITS_SUFFIX)?;
+    let (unit, hours_per_day) = content.split_once(',')?;
+    let hours_per_day = hours_per_day.trim().strip_suffix("h/day")?.parse().ok()?;
+    Some(EstimateUnits {
+        unit: EstimateUnit::from_suffix(unit.trim())?,
+        hours_per_day,
+    })
+}
+
+/// Returns the indentation width and the text after the bullet, or `None` if `line` is not a list item.
+/// Accepts `-`, `*`, `+` and numbered (`1.`, `1)`) bullets; a tab counts as four spaces.
+fn parse_list_item(line: &str) -> Option<(usize, &str)> {
+    let content = line.trim_start();
+    let indent = line[..line.len() - content.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+
+    let item = if let Some(item) = content
+        .strip_prefix("- ")
+        .or_else(|| content.strip_prefix("* "))
+        .or_else(|| content.strip_prefix("+ "))
+    {
+        item
+    } else {
+        let digits = content.find(|c: char| !c.is_ascii_digit())?;
+        if digits == 0 {
+            return None;
+        }
+        content[digits..]
+            .strip_prefix(". ")
+            .or_else(|| content[digits..].strip_prefix(") "))?
+    };
+    Some((indent, item.trim()))
+}
+
+/// Splits `"Login page (8h)"` into the name and the parsed estimate.
+pub(crate) fn split_estimate<'a>(
+    item: &'a str,
+    units: &EstimateUnits,
+) -> (&'a str, Option<ThreePointEstimate>) {
+    if let Some(without_paren) = item.strip_suffix(')') {
+        if let Some(open) = without_paren.rfind('(') {
+            if let Ok(estimate) = ThreePointEstimate::parse(&without_paren[open + 1..], units) {
+                return (without_paren[..open].trim_end(), Some(estimate));
+            }
+        }
+    }
+    (item, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_names_estimates_and_units() {
+        let mut app = EstimateApp::new();
+        app.units = EstimateUnits {
+            unit: EstimateUnit::Days,
+            hours_per_day: 6.0,
+        };
+        let phase = app.add_task("Phase (2)");
+        let build = app
+            .add_child_task(&phase, "Build (v2) (draft)", 12.0)
+            .unwrap();
+        let test = app.add_child_task(&phase, "Test", 0.0).unwrap();
+        app.find_mut_task(&test)
+            .unwrap()
+            .set_three_point(ThreePointEstimate::new(6.0, 12.0, 30.0));
+
+        let imported = from_markdown(&to_markdown(&app));
+
+        assert_eq!(imported.units, app.units);
+        let tasks = imported.get_tasks();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].name, "Phase (2)");
+        assert_eq!(tasks[0].estimate, 0.0);
+        let children = &tasks[0].children;
+        assert_eq!(children[0].name, "Build (v2) (draft)");
+        assert_eq!(
+            children[0].estimate,
+            app.find_task(&build).unwrap().estimate
+        );
+        assert_eq!(children[1].name, "Test");
+        assert_eq!(
+            children[1].three_point,
+            app.find_task(&test).unwrap().three_point
+        );
+    }
+
+    #[test]
+    fn round_trips_estimates_the_display_unit_would_round() {
+        let mut app = EstimateApp::new();
+        app.units = EstimateUnits {
+            unit: EstimateUnit::Days,
+            hours_per_day: 8.0,
+        };
+        let hour = app.add_task("One hour");
+        app.find_mut_task(&hour).unwrap().estimate = 1.0;
+        let range = app.add_task("Range");
+        app.find_mut_task(&range)
+            .unwrap()
+            .set_three_point(ThreePointEstimate::new(1.0, 8.0, 100.0 / 3.0));
+        let even = app.add_task("Two days");
+        app.find_mut_task(&even).unwrap().estimate = 16.0;
+
+        let markdown = to_markdown(&app);
+        assert!(markdown.contains("- One hour (1h)"), "{markdown}");
+        assert!(markdown.contains("- Two days (2d)"), "{markdown}");
+        let imported = from_markdown(&markdown);
+
+        for (imported, task) in imported.get_tasks().iter().zip(app.get_tasks()) {
+            assert_eq!(imported.estimate, task.estimate, "{}", task.name);
+            assert_eq!(imported.three_point, task.three_point, "{}", task.name);
+        }
+    }
+
+    #[test]
+    fn reads_annotations_in_default_units_without_a_comment() {
+        let app = from_markdown("- Design (1d)\n");
+        assert_eq!(app.units, EstimateUnits::default());
+        assert_eq!(app.get_tasks()[0].estimate, 8.0);
+    }
+}
@@ -0,0 +1,65 @@
+//! Exports the task tree as a Mermaid `mindmap` diagram, which wikis and Markdown viewers render.
+
+use std::collections::HashMap;
+
+use crate::label::estimate_summary;
+use crate::{EstimateApp, Rollup, Task};
+
+const INDENT: &str = "  ";
+
+/// Writes the document as a mindmap rooted at a circle with the total.
+/// Every task becomes a box labelled with its name, estimate and rollup.
+pub fn to_mermaid(app: &EstimateApp) -> String {
+    let rollups = app.rollups();
+    let mut mermaid = String::from("mindmap\n");
+    mermaid.push_str(&format!(
+        "{}root((\"{}\"))\n",
+        INDENT,
+        escape(&format!("Root · {}", app.units.format(app.total())))
+    ));
+    let mut next_id = 0;
+    for task in app.get_tasks() {
+        write_node(app, task, 2, &rollups, &mut next_id, &mut mermaid);
+    }
+    mermaid
+}
+
+fn write_node(
+    app: &EstimateApp,
+    task: &Task,
+    depth: usize,
+    rollups: &HashMap<String, Rollup>,
+    next_id: &mut usize,
+    mermaid: &mut String,
+) {
+    let label = format!(
+        "{} · {}",
+        task.name,
+        estimate_summary(task, rollups.get(&task.id), &app.units)
+    );
+    // Task IDs may contain characters Mermaid doesn't accept in node IDs.
+    mermaid.push_str(&format!(
+        "{}n{}[\"{}\"]\n",
+        INDENT.repeat(depth),
+        next_id,
+        escape(&label)
+    ));
+    *next_id += 1;
+    for child in &task.children {
+        write_node(app, child, depth + 1, rollups, next_id, mermaid);
+    }
+}
+
+/// Replaces characters that would end a quoted Mermaid label or the line with entity codes.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '#' => escaped.push_str("#35;"),
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
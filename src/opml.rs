@@ -0,0 +1,255 @@
+//! Reads and writes OPML 2.0 outlines, the exchange format of most outliners.
+//!
+//! Each task is an `<outline>` with its name in `text`, `Task::id` in `id` and
+//! `Task::estimate` in `estimate`. A three-point range adds `optimistic` and `pessimistic`.
+//! Estimates are written as plain numbers in hours, or story points for documents estimated in
+//! points; on import `estimate` may also use units such as `2d`, and outlines without the
+//! attribute fall back to a trailing `(8h)` in the text.
+
+use roxmltree::Node;
+
+use crate::{
+    import::{task_path, Import, ImportIssue},
+    markdown,
+    xml::escape,
+    EstimateApp, EstimateUnits, RangeParseError, Task, ThreePointEstimate,
+};
+
+const TITLE: &str = "Estimates";
+
+#[derive(Debug)]
+pub enum OpmlError {
+    Xml(roxmltree::Error),
+    /// The document is XML, but not `<opml>` with a `<body>`.
+    NotOpml,
+}
+
+impl std::fmt::Display for OpmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpmlError::Xml(error) => write!(f, "Not a valid XML file: {}", error),
+            OpmlError::NotOpml => write!(f, "Not an OPML outline, expected <opml> with a <body>"),
+        }
+    }
+}
+
+impl std::error::Error for OpmlError {}
+
+impl From<roxmltree::Error> for OpmlError {
+    fn from(error: roxmltree::Error) -> Self {
+        OpmlError::Xml(error)
+    }
+}
+
+pub fn to_opml(app: &EstimateApp) -> String {
+    let mut opml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    opml.push_str("<opml version=\"2.0\">\n");
+    opml.push_str(&format!(
+        "  <head>\n    <title>{}</title>\n  </head>\n",
+        TITLE
+    ));
+    opml.push_str("  <body>\n");
+    for task in app.get_tasks() {
+        write_outline(task, 2, &mut opml);
+    }
+    opml.push_str("  </body>\n</opml>\n");
+    opml
+}
+
+fn write_outline(task: &Task, depth: usize, opml: &mut String) {
+    opml.push_str(&"  ".repeat(depth));
+    opml.push_str(&format!(
+        "<outline text=\"{}\" id=\"{}\"",
+        escape(&task.name),
+        escape(&task.id)
+    ));
+    if task.estimate != 0.0 || task.three_point.is_some() {
+        opml.push_str(&format!(" estimate=\"{}\"", task.estimate));
+    }
+    if let Some(range) = task.three_point {
+        opml.push_str(&format!(
+            " optimistic=\"{}\" pessimistic=\"{}\"",
+            range.optimistic, range.pessimistic
+        ));
+    }
+    if task.children.is_empty() {
+        opml.push_str("/>\n");
+        return;
+    }
+    opml.push_str(">\n");
+    for child in &task.children {
+        write_outline(child, depth + 1, opml);
+    }
+    opml.push_str(&"  ".repeat(depth));
+    opml.push_str("</outline>\n");
+}
+
+/// Reads an OPML outline into a new document using `units` for estimates with a unit.
+/// Missing or duplicate IDs are replaced, estimates that can't be read are left out
+/// and reported as issues.
+pub fn from_opml(text: &str, units: EstimateUnits) -> Result<Import, OpmlError> {
+    let xml = roxmltree::Document::parse(text)?;
+    let root = xml.root_element();
+    let body = root
+        .children()
+        .find(|child| child.has_tag_name("body"))
+        .filter(|_| root.has_tag_name("opml"))
+        .ok_or(OpmlError::NotOpml)?;
+
+    let mut import = Import {
+        estimate_app: EstimateApp::new(),
+        issues: Vec::new(),
+    };
+    import.estimate_app.units = units;
+    for outline in outlines(body) {
+        let task = read_outline(outline, "", &units, &mut import.issues);
+        import.estimate_app.get_tasks_mut().push(task);
+    }
+    let repaired = import.estimate_app.repair_task_ids();
+    if repaired > 0 {
+        log::info!("Gave {} imported tasks a new ID", repaired);
+    }
+    Ok(import)
+}
+
+fn read_outline(
+    outline: Node<'_, '_>,
+    parent_path: &str,
+    units: &EstimateUnits,
+    issues: &mut Vec<ImportIssue>,
+) -> Task {
+    let text = outline.attribute("text").unwrap_or("").trim();
+    let (name, text_estimate) = markdown::split_estimate(text, units);
+    let id = outline.attribute("id").unwrap_or("");
+
+    let (mut task, estimate) = match outline.attribute("estimate") {
+        Some(estimate) => {
+            let path = task_path(parent_path, text);
+            let estimate = match read_estimate(outline, estimate, units) {
+                Ok(estimate) => Some(estimate),
+                Err(message) => {
+                    issues.push(ImportIssue { path, message });
+                    None
+                }
+            };
+            (Task::new(id, text, 0.0), estimate)
+        }
+        None => (Task::new(id, name, 0.0), text_estimate),
+    };
+    if let Some(estimate) = estimate {
+        task.set_three_point(estimate);
+    }
+    let path = task_path(parent_path, &task.name);
+    task.children = outlines(outline)
+        .map(|child| read_outline(child, &path, units, issues))
+        .collect();
+    task
+}
+
+/// Reads `estimate` and the optional `optimistic` / `pessimistic` attributes of `outline`,
+/// checked the way `ThreePointEstimate::parse` checks a typed range.
+fn read_estimate(
+    outline: Node<'_, '_>,
+    estimate: &str,
+    units: &EstimateUnits,
+) -> Result<ThreePointEstimate, String> {
+    let parse = |attribute: &str, value: &str| {
+        // Plain numbers are already in the document's base unit, see `to_opml`.
+        let number = match value.trim().parse::<f32>() {
+            Ok(number) => Ok(number),
+            Err(_) => units.parse(value).map_err(|error| error.to_string()),
+        };
+        number
+            .and_then(|number| {
+                if number.is_finite() && number >= 0.0 {
+                    Ok(number)
+                } else {
+                    Err("estimates must be finite and not negative".to_owned())
+                }
+            })
+            .map_err(|error| {
+                format!(
+                    "{} attribute \"{}\" is not an estimate: {}",
+                    attribute, value, error
+                )
+            })
+    };
+    let most_likely = parse("estimate", estimate)?;
+    let optimistic = match outline.attribute("optimistic") {
+        Some(value) => parse("optimistic", value)?,
+        None => most_likely,
+    };
+    let pessimistic = match outline.attribute("pessimistic") {
+        Some(value) => parse("pessimistic", value)?,
+        None => most_likely,
+    };
+    let range = ThreePointEstimate::new(optimistic, most_likely, pessimistic);
+    if optimistic <= most_likely && most_likely <= pessimistic {
+        Ok(range)
+    } else {
+        Err(format!(
+            "estimate {} is not an estimate: {}",
+            range.format(units),
+            RangeParseError::Unordered
+        ))
+    }
+}
+
+fn outlines<'a, 'input>(node: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(|child| child.has_tag_name("outline"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(outline: &str) -> Import {
+        let text = format!(
+            r#"<opml version="2.0"><body><outline text="Phase">{}</outline></body></opml>"#,
+            outline
+        );
+        from_opml(&text, EstimateUnits::default()).unwrap()
+    }
+
+    #[test]
+    fn reports_estimates_it_cannot_use() {
+        for (outline, message) in [
+            (
+                r#"<outline text="Unordered" estimate="4" optimistic="6" pessimistic="8"/>"#,
+                "Expected optimistic <= most likely <= pessimistic",
+            ),
+            (
+                r#"<outline text="Negative" estimate="4" optimistic="-3"/>"#,
+                "not negative",
+            ),
+            (r#"<outline text="Infinite" estimate="inf"/>"#, "finite"),
+            (r#"<outline text="Not a number" estimate="NaN"/>"#, "finite"),
+            (
+                r#"<outline text="Not numeric" estimate="soon"/>"#,
+                "is not an estimate",
+            ),
+        ] {
+            let import = import(outline);
+            let task = &import.estimate_app.get_tasks()[0].children[0];
+            assert_eq!(task.estimate, 0.0, "{outline}");
+            assert_eq!(task.three_point, None, "{outline}");
+            assert_eq!(import.issues.len(), 1, "{outline}");
+            let issue = &import.issues[0];
+            assert_eq!(issue.path, format!("Phase / {}", task.name));
+            assert!(issue.message.contains(message), "{issue}");
+        }
+    }
+
+    #[test]
+    fn reads_ordered_ranges_and_units() {
+        let import =
+            import(r#"<outline text="Build" estimate="1d" optimistic="2" pessimistic="2d"/>"#);
+        assert!(import.issues.is_empty());
+        let task = &import.estimate_app.get_tasks()[0].children[0];
+        assert_eq!(
+            task.three_point,
+            Some(ThreePointEstimate::new(2.0, 8.0, 16.0))
+        );
+    }
+}
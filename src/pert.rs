@@ -0,0 +1,229 @@
+use crate::duration::{DurationParseError, EstimateUnits};
+
+/// A three-point estimate (optimistic / most likely / pessimistic) for a single task.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ThreePointEstimate {
+    pub optimistic: f32,
+    pub most_likely: f32,
+    pub pessimistic: f32,
+}
+
+impl ThreePointEstimate {
+    pub fn new(optimistic: f32, most_likely: f32, pessimistic: f32) -> Self {
+        Self {
+            optimistic,
+            most_likely,
+            pessimistic,
+        }
+    }
+
+    /// A range where all three points are the same value, used for single-value estimates.
+    pub fn degenerate(estimate: f32) -> Self {
+        Self::new(estimate, estimate, estimate)
+    }
+
+    /// Parses either a single estimate (a degenerate range) or `"o/m/p"`, e.g. `"4h/1d/3d"`.
+    /// Every part accepts the same syntax as `EstimateUnits::parse`.
+    pub fn parse(text: &str, units: &EstimateUnits) -> Result<Self, RangeParseError> {
+        let parts = text
+            .split('/')
+            .map(|part| units.parse(part))
+            .collect::<Result<Vec<_>, _>>()?;
+        match parts[..] {
+            [estimate] => Ok(Self::degenerate(estimate)),
+            [optimistic, most_likely, pessimistic] => {
+                if optimistic <= most_likely && most_likely <= pessimistic {
+                    Ok(Self::new(optimistic, most_likely, pessimistic))
+                } else {
+                    Err(RangeParseError::Unordered)
+                }
+            }
+            _ => Err(RangeParseError::WrongPartCount(parts.len())),
+        }
+    }
+
+    /// Formats as `"o/m/p"` in the document's display unit, the inverse of `parse`.
+    pub fn format(&self, units: &EstimateUnits) -> String {
+        format!(
+            "{}/{}/{}",
+            units.format(self.optimistic),
+            units.format(self.most_likely),
+            units.format(self.pessimistic)
+        )
+    }
+
+    /// Like `format`, without rounding, see `EstimateUnits::format_exact`.
+    pub fn format_exact(&self, units: &EstimateUnits) -> String {
+        format!(
+            "{}/{}/{}",
+            units.format_exact(self.optimistic),
+            units.format_exact(self.most_likely),
+            units.format_exact(self.pessimistic)
+        )
+    }
+
+    pub fn is_degenerate(&self) -> bool {
+        self.optimistic == self.most_likely && self.most_likely == self.pessimistic
+    }
+
+    /// PERT expected value: `(o + 4m + p) / 6`.
+    pub fn expected(&self) -> f32 {
+        (self.optimistic + 4.0 * self.most_likely + self.pessimistic) / 6.0
+    }
+
+    /// PERT standard deviation: `(p - o) / 6`.
+    pub fn std_dev(&self) -> f32 {
+        (self.pessimistic - self.optimistic) / 6.0
+    }
+
+    pub fn variance(&self) -> f32 {
+        self.std_dev().powi(2)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RangeParseError {
+    Duration(DurationParseError),
+    WrongPartCount(usize),
+    /// The values were not optimistic <= most likely <= pessimistic.
+    Unordered,
+}
+
+impl From<DurationParseError> for RangeParseError {
+    fn from(error: DurationParseError) -> Self {
+        RangeParseError::Duration(error)
+    }
+}
+
+impl std::fmt::Display for RangeParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RangeParseError::Duration(error) => error.fmt(f),
+            RangeParseError::WrongPartCount(count) => write!(
+                f,
+                "Expected one value or optimistic/most likely/pessimistic, got {} values",
+                count
+            ),
+            RangeParseError::Unordered => {
+                write!(f, "Expected optimistic <= most likely <= pessimistic")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RangeParseError {}
+
+/// Expected value and variance of a whole subtree.
+/// Variances are summed (tasks are assumed independent), so the standard deviation is derived.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PertSummary {
+    pub expected: f32,
+    pub variance: f32,
+}
+
+impl PertSummary {
+    pub fn std_dev(&self) -> f32 {
+        self.variance.sqrt()
+    }
+
+    /// Formats as `"expected ± std dev"` in the document's display unit.
+    pub fn format(&self, units: &EstimateUnits) -> String {
+        format!(
+            "{} ± {}",
+            units.format(self.expected),
+            units.format(self.std_dev())
+        )
+    }
+}
+
+impl std::ops::Add for PertSummary {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            expected: self.expected + other.expected,
+            variance: self.variance + other.variance,
+        }
+    }
+}
+
+impl std::iter::Sum for PertSummary {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |a, b| a + b)
+    }
+}
+
+impl From<ThreePointEstimate> for PertSummary {
+    fn from(estimate: ThreePointEstimate) -> Self {
+        Self {
+            expected: estimate.expected(),
+            variance: estimate.variance(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EstimateApp;
+
+    #[test]
+    fn computes_expected_value_and_spread() {
+        let range = ThreePointEstimate::new(2.0, 4.0, 12.0);
+        assert_eq!(range.expected(), 5.0);
+        assert_eq!(range.std_dev(), 10.0 / 6.0);
+        assert!(!range.is_degenerate());
+
+        let single = ThreePointEstimate::degenerate(4.0);
+        assert!(single.is_degenerate());
+        assert_eq!(single.expected(), 4.0);
+        assert_eq!(single.std_dev(), 0.0);
+    }
+
+    #[test]
+    fn parses_single_values_and_ranges() {
+        let units = EstimateUnits::default();
+        assert_eq!(
+            ThreePointEstimate::parse("4", &units),
+            Ok(ThreePointEstimate::degenerate(4.0))
+        );
+        assert_eq!(
+            ThreePointEstimate::parse("2/1d/2d", &units),
+            Ok(ThreePointEstimate::new(2.0, 8.0, 16.0))
+        );
+        assert_eq!(
+            ThreePointEstimate::parse("8/4/12", &units),
+            Err(RangeParseError::Unordered)
+        );
+        assert_eq!(
+            ThreePointEstimate::parse("2/4", &units),
+            Err(RangeParseError::WrongPartCount(2))
+        );
+        assert!(matches!(
+            ThreePointEstimate::parse("2/x/4", &units),
+            Err(RangeParseError::Duration(_))
+        ));
+    }
+
+    #[test]
+    fn rolls_up_expected_values_and_variances() {
+        let mut app = EstimateApp::new();
+        let parent = app.add_task("Parent");
+        let first = app.add_child_task(&parent, "First", 0.0).unwrap();
+        let second = app.add_child_task(&parent, "Second", 0.0).unwrap();
+        app.find_mut_task(&first)
+            .unwrap()
+            .set_three_point(ThreePointEstimate::new(0.0, 3.0, 6.0));
+        app.find_mut_task(&second)
+            .unwrap()
+            .set_three_point(ThreePointEstimate::new(6.0, 6.0, 18.0));
+
+        let total = app.pert_total();
+        assert_eq!(total.expected, 3.0 + 8.0);
+        // Variances add up, standard deviations don't.
+        assert_eq!(total.variance, 1.0 + 4.0);
+        assert_eq!(total.std_dev(), 5.0_f32.sqrt());
+        let parent = app.find_task(&parent).unwrap();
+        assert_eq!(app.pert_rollup(parent), total);
+    }
+}
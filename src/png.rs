@@ -0,0 +1,219 @@
+//! Renders the mindmap to a PNG image on the CPU, for machines without a GPU or display.
+//! The layout and shapes are the same as the SVG export and the central panel.
+
+use ab_glyph::{Font as _, ScaleFont as _};
+use ecolor::Color32;
+use emath::{Align2, Pos2, Rect, Vec2};
+use tiny_skia::{FillRule, Paint, PathBuilder, Pixmap, Transform};
+
+use crate::scene::{mindmap_scene, Shape, Stroke, FONT_SIZE};
+use crate::{text, EstimateApp};
+
+/// Scale used when exporting from the app, sharp enough for high-DPI screens and documents.
+pub const DEFAULT_SCALE: f32 = 2.0;
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// The scale is not a positive number.
+    InvalidScale(f32),
+    /// The image would be too large to allocate at this scale.
+    TooLarge {
+        width: u32,
+        height: u32,
+    },
+    Encode(String),
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::InvalidScale(scale) => {
+                write!(f, "The scale must be a positive number, not {}", scale)
+            }
+            RenderError::TooLarge { width, height } => {
+                write!(f, "A {}×{} image is too large to render", width, height)
+            }
+            RenderError::Encode(error) => write!(f, "Could not encode the PNG image: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// Renders the mindmap with every length multiplied by `scale`.
+pub fn to_png(app: &EstimateApp, scale: f32) -> Result<Vec<u8>, RenderError> {
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err(RenderError::InvalidScale(scale));
+    }
+    let scene = mindmap_scene(app);
+    let size = scene.bounds.size() * scale;
+    let (width, height) = (size.x.ceil() as u32, size.y.ceil() as u32);
+    let mut pixmap = Pixmap::new(width, height).ok_or(RenderError::TooLarge { width, height })?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+
+    let transform = Transform::from_row(
+        scale,
+        0.0,
+        0.0,
+        scale,
+        -scene.bounds.min.x * scale,
+        -scene.bounds.min.y * scale,
+    );
+    for shape in &scene.shapes {
+        match shape {
+            Shape::Rect {
+                rect,
+                rounding,
+                fill,
+                stroke,
+            } => {
+                if let Some(path) = rounded_rect_path(*rect, *rounding) {
+                    draw_path(&mut pixmap, &path, *fill, *stroke, transform);
+                }
+            }
+            Shape::Line { points, stroke } => {
+                let mut builder = PathBuilder::new();
+                builder.move_to(points[0].x, points[0].y);
+                builder.line_to(points[1].x, points[1].y);
+                if let Some(path) = builder.finish() {
+                    draw_path(&mut pixmap, &path, Color32::TRANSPARENT, *stroke, transform);
+                }
+            }
+            Shape::Circle {
+                center,
+                radius,
+                fill,
+                stroke,
+            } => {
+                if let Some(path) = PathBuilder::from_circle(center.x, center.y, *radius) {
+                    draw_path(&mut pixmap, &path, *fill, *stroke, transform);
+                }
+            }
+            Shape::Text {
+                position,
+                anchor,
+                text,
+                color,
+            } => {
+                let position = Pos2::ZERO + (*position - scene.bounds.min) * scale;
+                draw_text(&mut pixmap, position, *anchor, text, *color, scale);
+            }
+        }
+    }
+
+    pixmap
+        .encode_png()
+        .map_err(|error| RenderError::Encode(error.to_string()))
+}
+
+fn draw_path(
+    pixmap: &mut Pixmap,
+    path: &tiny_skia::Path,
+    fill: Color32,
+    stroke: Stroke,
+    transform: Transform,
+) {
+    if fill.a() > 0 {
+        let paint = paint(fill);
+        pixmap.fill_path(path, &paint, FillRule::Winding, transform, None);
+    }
+    if !stroke.is_empty() {
+        let paint = paint(stroke.color);
+        let stroke = tiny_skia::Stroke {
+            width: stroke.width,
+            ..Default::default()
+        };
+        pixmap.stroke_path(path, &paint, &stroke, transform, None);
+    }
+}
+
+fn paint(color: Color32) -> Paint<'static> {
+    let mut paint = Paint::default();
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    paint.set_color_rgba8(r, g, b, a);
+    paint.anti_alias = true;
+    paint
+}
+
+fn rounded_rect_path(rect: Rect, rounding: f32) -> Option<tiny_skia::Path> {
+    let r = rounding.min(rect.width() / 2.0).min(rect.height() / 2.0);
+    let (left, top, right, bottom) = (rect.min.x, rect.min.y, rect.max.x, rect.max.y);
+    let mut builder = PathBuilder::new();
+    builder.move_to(left + r, top);
+    builder.line_to(right - r, top);
+    builder.quad_to(right, top, right, top + r);
+    builder.line_to(right, bottom - r);
+    builder.quad_to(right, bottom, right - r, bottom);
+    builder.line_to(left + r, bottom);
+    builder.quad_to(left, bottom, left, bottom - r);
+    builder.line_to(left, top + r);
+    builder.quad_to(left, top, left + r, top);
+    builder.close();
+    builder.finish()
+}
+
+/// Draws a single line of text at pixel coordinates, aligned like `egui::Painter::text`.
+fn draw_text(
+    pixmap: &mut Pixmap,
+    position: Pos2,
+    anchor: Align2,
+    text: &str,
+    color: Color32,
+    scale: f32,
+) {
+    let font = text::font();
+    let scaled = text::scaled_font(FONT_SIZE * scale);
+
+    let mut width = 0.0;
+    let mut previous = None;
+    let glyph_ids: Vec<_> = text.chars().map(|c| scaled.glyph_id(c)).collect();
+    let mut offsets = Vec::with_capacity(glyph_ids.len());
+    for &id in &glyph_ids {
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        offsets.push(width);
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    let line = anchor.anchor_size(position, Vec2::new(width, scaled.height()));
+    let baseline = line.min.y + scaled.ascent();
+
+    let [r, g, b, a] = color.to_array();
+    let pixmap_width = pixmap.width() as i32;
+    let pixmap_height = pixmap.height() as i32;
+    let pixels = pixmap.pixels_mut();
+    for (id, offset) in glyph_ids.into_iter().zip(offsets) {
+        let glyph = id.with_scale_and_position(
+            scaled.scale(),
+            ab_glyph::point(line.min.x + offset, baseline),
+        );
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|x, y, coverage| {
+            let x = bounds.min.x as i32 + x as i32;
+            let y = bounds.min.y as i32 + y as i32;
+            if x < 0 || y < 0 || x >= pixmap_width || y >= pixmap_height {
+                return;
+            }
+            let pixel = &mut pixels[(y * pixmap_width + x) as usize];
+            // Source-over blending; `Color32` is premultiplied like the pixmap.
+            let coverage = coverage.clamp(0.0, 1.0);
+            let source_alpha = a as f32 / 255.0 * coverage;
+            let blend = |source: u8, destination: u8| {
+                (source as f32 * coverage + destination as f32 * (1.0 - source_alpha)).round() as u8
+            };
+            let alpha = blend(a, pixel.alpha());
+            if let Some(blended) = tiny_skia::PremultipliedColorU8::from_rgba(
+                blend(r, pixel.red()).min(alpha),
+                blend(g, pixel.green()).min(alpha),
+                blend(b, pixel.blue()).min(alpha),
+                alpha,
+            ) {
+                *pixel = blended;
+            }
+        });
+    }
+}
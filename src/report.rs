@@ -0,0 +1,165 @@
+//! A self-contained HTML report for people who don't have the app: summary statistics,
+//! the share of each top-level task in the total and a collapsible breakdown.
+
+use std::collections::HashMap;
+
+use crate::label::estimate_label;
+use crate::{xml::escape, EstimateApp, EstimateUnits, Rollup, Task};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 60em; color: #222; }
+table { border-collapse: collapse; }
+th, td { padding: 0.25em 0.75em; text-align: left; border-bottom: 1px solid #ddd; }
+td.number { text-align: right; font-variant-numeric: tabular-nums; }
+.bar { background: #add8e6; height: 0.8em; }
+.tree, .tree ul { list-style: none; padding-left: 1.5em; }
+.tree > li { margin-bottom: 0.25em; }
+.leaf { padding-left: 1em; }
+.estimate { color: #666; margin-left: 0.5em; }
+.total { font-weight: bold; margin-left: 0.5em; }
+.conflict { color: #c60; margin-left: 0.5em; }
+";
+
+const SCRIPT: &str = "
+function setOpen(open) {
+  document.querySelectorAll('.tree details').forEach(function (details) { details.open = open; });
+}
+";
+
+/// Writes the report as a single HTML page with inline styles and no external resources.
+pub fn to_html(app: &EstimateApp) -> String {
+    let rollups = app.rollups();
+    let units = &app.units;
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n<title>Estimate report</title>\n");
+    html.push_str(&format!("<style>{}</style>\n", STYLE));
+    html.push_str(&format!("<script>{}</script>\n", SCRIPT));
+    html.push_str("</head>\n<body>\n<h1>Estimate report</h1>\n");
+
+    write_summary(app, &rollups, &mut html);
+    write_shares(app, &rollups, &mut html);
+
+    html.push_str("<h2>Breakdown</h2>\n");
+    html.push_str(
+        "<p><button onclick=\"setOpen(true)\">Expand all</button> \
+         <button onclick=\"setOpen(false)\">Collapse all</button></p>\n",
+    );
+    html.push_str("<ul class=\"tree\">\n");
+    for task in app.get_tasks() {
+        write_task(task, &rollups, units, &mut html);
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+    html
+}
+
+fn write_summary(app: &EstimateApp, rollups: &HashMap<String, Rollup>, html: &mut String) {
+    let units = &app.units;
+    let tasks = app.flatten_tasks();
+    let leaves: Vec<&Task> = tasks
+        .iter()
+        .copied()
+        .filter(|task| task.children.is_empty())
+        .collect();
+    let unestimated = leaves.iter().filter(|task| task.estimate == 0.0).count();
+    let conflicts = rollups.values().filter(|rollup| rollup.conflict).count();
+    let largest = leaves
+        .iter()
+        .max_by(|a, b| a.estimate.total_cmp(&b.estimate))
+        .map(|task| format!("{} ({})", task.name, units.format(task.estimate)));
+    let depth = app.get_tasks().iter().map(depth).max().unwrap_or(0);
+
+    let mut rows = vec![
+        ("Total", units.format(app.total())),
+        ("PERT expected", app.pert_total().format(units)),
+        ("Rollup", app.rollup_mode.to_string()),
+        ("Tasks", tasks.len().to_string()),
+        ("Top-level tasks", app.get_tasks().len().to_string()),
+        ("Leaf tasks", leaves.len().to_string()),
+        ("Unestimated leaf tasks", unestimated.to_string()),
+        (
+            "Tasks conflicting with their children",
+            conflicts.to_string(),
+        ),
+        ("Deepest level", depth.to_string()),
+    ];
+    if let Some(largest) = largest {
+        rows.push(("Largest leaf task", largest));
+    }
+
+    html.push_str("<h2>Summary</h2>\n<table>\n");
+    for (label, value) in rows {
+        html.push_str(&format!(
+            "<tr><th>{}</th><td>{}</td></tr>\n",
+            label,
+            escape(&value)
+        ));
+    }
+    html.push_str("</table>\n");
+}
+
+fn write_shares(app: &EstimateApp, rollups: &HashMap<String, Rollup>, html: &mut String) {
+    let total = app.total();
+    html.push_str("<h2>Share of total</h2>\n<table>\n");
+    html.push_str("<tr><th>Task</th><th>Total</th><th>Share</th><th></th></tr>\n");
+    for task in app.get_tasks() {
+        let task_total = rollups
+            .get(&task.id)
+            .map_or(task.estimate, |rollup| rollup.total);
+        let share = if total > 0.0 { task_total / total } else { 0.0 };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{:.1}%</td>\
+             <td style=\"width: 15em\"><div class=\"bar\" style=\"width: {:.1}%\"></div></td></tr>\n",
+            escape(&task.name),
+            escape(&app.units.format(task_total)),
+            share * 100.0,
+            share * 100.0
+        ));
+    }
+    html.push_str("</table>\n");
+}
+
+/// A task as a list item, with its subtasks in a `<details>` element that can be collapsed.
+fn write_task(
+    task: &Task,
+    rollups: &HashMap<String, Rollup>,
+    units: &EstimateUnits,
+    html: &mut String,
+) {
+    let rollup = rollups.get(&task.id);
+    let mut line = format!(
+        "<span class=\"name\">{}</span><span class=\"estimate\">{}</span>",
+        escape(&task.name),
+        escape(&estimate_label(task, units))
+    );
+    if task.children.is_empty() {
+        html.push_str(&format!("<li class=\"leaf\">{}</li>\n", line));
+        return;
+    }
+
+    if let Some(rollup) = rollup {
+        line.push_str(&format!(
+            "<span class=\"total\">Σ {}</span>",
+            escape(&units.format(rollup.total))
+        ));
+        if rollup.conflict {
+            line.push_str(&format!(
+                "<span class=\"conflict\" title=\"The own estimate differs from the children\">\
+                 ! children add up to {}</span>",
+                escape(&units.format(rollup.children))
+            ));
+        }
+    }
+    html.push_str(&format!(
+        "<li><details open><summary>{}</summary>\n<ul>\n",
+        line
+    ));
+    for child in &task.children {
+        write_task(child, rollups, units, html);
+    }
+    html.push_str("</ul>\n</details></li>\n");
+}
+
+/// Number of levels in the subtree of `task`, 1 for a leaf.
+fn depth(task: &Task) -> usize {
+    1 + task.children.iter().map(depth).max().unwrap_or(0)
+}
@@ -0,0 +1,131 @@
+/// How a parent's own estimate is combined with the estimates of its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, serde::Serialize)]
+pub enum RollupMode {
+    /// Parents are the sum of their children, their own estimate is ignored.
+    SumChildren,
+    /// A parent's own estimate is extra work on top of its children.
+    #[default]
+    OwnPlusChildren,
+    /// A parent's own estimate, if set, replaces the sum of its children.
+    OwnOverridesChildren,
+    /// The larger of a parent's own estimate and the sum of its children.
+    Max,
+}
+
+/// Which values a rolled-up total is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollupSource {
+    Own,
+    Children,
+    Both,
+}
+
+impl RollupMode {
+    pub const ALL: [RollupMode; 4] = [
+        RollupMode::SumChildren,
+        RollupMode::OwnPlusChildren,
+        RollupMode::OwnOverridesChildren,
+        RollupMode::Max,
+    ];
+
+    /// Decides which values make up a task's total, given its own estimate and its children's sum.
+    pub fn source(self, own: f32, children: f32, has_children: bool) -> RollupSource {
+        if !has_children {
+            return RollupSource::Own;
+        }
+        match self {
+            RollupMode::SumChildren => RollupSource::Children,
+            RollupMode::OwnPlusChildren => RollupSource::Both,
+            RollupMode::OwnOverridesChildren if own > 0.0 => RollupSource::Own,
+            RollupMode::OwnOverridesChildren => RollupSource::Children,
+            RollupMode::Max if own >= children => RollupSource::Own,
+            RollupMode::Max => RollupSource::Children,
+        }
+    }
+
+    pub fn combine(self, own: f32, children: f32, has_children: bool) -> f32 {
+        match self.source(own, children, has_children) {
+            RollupSource::Own => own,
+            RollupSource::Children => children,
+            RollupSource::Both => own + children,
+        }
+    }
+
+    /// Whether own and children estimates describe the same work in this mode,
+    /// so a parent estimate that differs from its children is a conflict.
+    pub fn own_competes_with_children(self) -> bool {
+        self != RollupMode::OwnPlusChildren
+    }
+}
+
+impl std::fmt::Display for RollupMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RollupMode::SumChildren => "Sum of children",
+            RollupMode::OwnPlusChildren => "Own plus children",
+            RollupMode::OwnOverridesChildren => "Own overrides children",
+            RollupMode::Max => "Max of own and children",
+        })
+    }
+}
+
+/// The rolled-up estimate of one task, computed by `EstimateApp::rollups`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rollup {
+    /// Sum of the children's rolled-up totals.
+    pub children: f32,
+    pub total: f32,
+    /// The parent's own estimate disagrees with the sum of its children.
+    pub conflict: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EstimateApp;
+
+    #[test]
+    fn combines_own_and_children_per_mode() {
+        let cases = [
+            (RollupMode::SumChildren, 4.0, 6.0, 6.0),
+            (RollupMode::OwnPlusChildren, 4.0, 6.0, 10.0),
+            (RollupMode::OwnOverridesChildren, 4.0, 6.0, 4.0),
+            (RollupMode::OwnOverridesChildren, 0.0, 6.0, 6.0),
+            (RollupMode::Max, 4.0, 6.0, 6.0),
+            (RollupMode::Max, 8.0, 6.0, 8.0),
+        ];
+        for (mode, own, children, total) in cases {
+            assert_eq!(mode.combine(own, children, true), total, "{mode}");
+            // Leaves are always their own estimate.
+            assert_eq!(mode.combine(own, 0.0, false), own, "{mode}");
+        }
+    }
+
+    #[test]
+    fn rolls_up_the_tree_and_flags_conflicts() {
+        let mut app = EstimateApp::new();
+        app.rollup_mode = RollupMode::SumChildren;
+        let phase = app.add_task("Phase");
+        app.find_mut_task(&phase).unwrap().estimate = 4.0;
+        let build = app.add_child_task(&phase, "Build", 0.0).unwrap();
+        app.add_child_task(&build, "Backend", 8.0).unwrap();
+        app.add_child_task(&build, "Frontend", 2.0).unwrap();
+        app.add_child_task(&phase, "Review", 1.0).unwrap();
+
+        let rollups = app.rollups();
+        assert_eq!(rollups[&build].total, 10.0);
+        assert_eq!(rollups[&phase].children, 11.0);
+        assert_eq!(rollups[&phase].total, 11.0);
+        // The parent's own 4h competes with its children's 11h.
+        assert!(rollups[&phase].conflict);
+        assert!(!rollups[&build].conflict);
+        assert_eq!(app.total(), 11.0);
+
+        // A task can override the document's mode.
+        app.find_mut_task(&phase).unwrap().rollup_mode = Some(RollupMode::OwnPlusChildren);
+        let rollups = app.rollups();
+        assert_eq!(rollups[&phase].total, 15.0);
+        assert!(!rollups[&phase].conflict);
+        assert_eq!(app.total(), 15.0);
+    }
+}
@@ -0,0 +1,207 @@
+//! The mindmap as a list of plain shapes, laid out like on screen, for the image exporters.
+
+use ecolor::Color32;
+use emath::{Align2, Pos2, Rect, Vec2};
+
+use crate::layout::{compute_layout, line_centers, sum_position, EXPORT_CANVAS_SIZE};
+use crate::text::{line_height, text_width};
+use crate::EstimateApp;
+
+pub const ROUNDING: f32 = 5.0;
+/// Radius of the bubble showing a parent's rolled-up estimate.
+pub const SUM_RADIUS: f32 = 20.0;
+
+/// Font size of all text in the mindmap.
+pub(crate) const FONT_SIZE: f32 = 16.0;
+
+/// Empty space around the drawing.
+const MARGIN: f32 = 20.0;
+
+/// Outline of a shape, like `egui::Stroke`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Stroke {
+    pub width: f32,
+    pub color: Color32,
+}
+
+impl Stroke {
+    pub const NONE: Stroke = Stroke {
+        width: 0.0,
+        color: Color32::TRANSPARENT,
+    };
+
+    pub fn new(width: f32, color: Color32) -> Self {
+        Self { width, color }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0.0 || self.color == Color32::TRANSPARENT
+    }
+}
+
+pub(crate) enum Shape {
+    Rect {
+        rect: Rect,
+        rounding: f32,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    Line {
+        points: [Pos2; 2],
+        stroke: Stroke,
+    },
+    Circle {
+        center: Pos2,
+        radius: f32,
+        fill: Color32,
+        stroke: Stroke,
+    },
+    /// A single line of text, `anchor` tells which side of the text `position` is on,
+    /// as in `egui::Painter::text`.
+    Text {
+        position: Pos2,
+        anchor: Align2,
+        text: String,
+        color: Color32,
+    },
+}
+
+pub(crate) struct Scene {
+    /// In painting order, later shapes cover earlier ones.
+    pub shapes: Vec<Shape>,
+    /// Encloses every shape, including a margin.
+    pub bounds: Rect,
+}
+
+/// Lays out `app` the way the central panel does on a screen of `EXPORT_CANVAS_SIZE`,
+/// with the document's layout style.
+/// Connectors are drawn first, then boxes, then conflict markers and sum bubbles on top.
+pub(crate) fn mindmap_scene(app: &EstimateApp) -> Scene {
+    let layout = compute_layout(app, EXPORT_CANVAS_SIZE);
+    let units = &app.units;
+
+    let mut shapes = Vec::new();
+    for edge in &layout.edges {
+        for segment in edge.windows(2) {
+            shapes.push(Shape::Line {
+                points: [segment[0], segment[1]],
+                stroke: Stroke::new(1.5, Color32::DARK_GRAY),
+            });
+        }
+    }
+    node_shapes(
+        &mut shapes,
+        layout.root_rect,
+        &["Root", &layout.root_estimate],
+        0,
+    );
+    for node in &layout.nodes {
+        node_shapes(&mut shapes, node.rect, &node.lines(), node.depth_level);
+    }
+    for node in &layout.nodes {
+        let Some(rollup) = layout.rollups.get(&node.task_id) else {
+            continue;
+        };
+        if rollup.conflict {
+            conflict_shapes(&mut shapes, node.rect);
+        }
+        if node.has_children {
+            let center = sum_position(node.rect);
+            shapes.push(Shape::Circle {
+                center,
+                radius: SUM_RADIUS,
+                fill: Color32::LIGHT_BLUE,
+                stroke: Stroke::new(1.5, Color32::KHAKI),
+            });
+            shapes.push(Shape::Text {
+                position: center,
+                anchor: Align2::CENTER_CENTER,
+                text: units.format(rollup.total),
+                color: Color32::BLACK,
+            });
+        }
+    }
+
+    let bounds = shapes
+        .iter()
+        .fold(layout.root_rect, |bounds, shape| {
+            bounds.union(shape_bounds(shape))
+        })
+        .expand(MARGIN);
+    Scene { shapes, bounds }
+}
+
+/// Same as `task_drawer::paint_rectangle` for a task that isn't selected.
+fn node_shapes(shapes: &mut Vec<Shape>, rect: Rect, lines: &[&str], depth_level: usize) {
+    shapes.push(Shape::Rect {
+        rect,
+        rounding: ROUNDING,
+        fill: node_fill(depth_level),
+        stroke: Stroke::new(2.0, Color32::BLACK),
+    });
+    let positions = line_centers(rect, lines.len(), line_height(FONT_SIZE));
+    for (line, position) in lines.iter().zip(positions) {
+        shapes.push(Shape::Text {
+            position,
+            anchor: Align2::CENTER_CENTER,
+            text: (*line).to_owned(),
+            color: Color32::BLACK,
+        });
+    }
+}
+
+/// Same as `task_drawer::paint_conflict`.
+fn conflict_shapes(shapes: &mut Vec<Shape>, rect: Rect) {
+    shapes.push(Shape::Rect {
+        rect: rect.expand(4.0),
+        rounding: ROUNDING,
+        fill: Color32::TRANSPARENT,
+        stroke: Stroke::new(2.0, Color32::ORANGE),
+    });
+    shapes.push(Shape::Circle {
+        center: rect.right_top(),
+        radius: 10.0,
+        fill: Color32::ORANGE,
+        stroke: Stroke::NONE,
+    });
+    shapes.push(Shape::Text {
+        position: rect.right_top(),
+        anchor: Align2::CENTER_CENTER,
+        text: "!".to_owned(),
+        color: Color32::BLACK,
+    });
+}
+
+/// Area covered by `shape`.
+fn shape_bounds(shape: &Shape) -> Rect {
+    match shape {
+        Shape::Rect { rect, stroke, .. } => rect.expand(stroke.width / 2.0),
+        Shape::Line { points, stroke } => {
+            Rect::from_two_pos(points[0], points[1]).expand(stroke.width / 2.0)
+        }
+        Shape::Circle {
+            center,
+            radius,
+            stroke,
+            ..
+        } => Rect::from_center_size(*center, Vec2::splat(radius * 2.0 + stroke.width)),
+        Shape::Text {
+            position,
+            anchor,
+            text,
+            ..
+        } => {
+            let size = Vec2::new(text_width(text, FONT_SIZE), line_height(FONT_SIZE));
+            anchor.anchor_size(*position, size)
+        }
+    }
+}
+
+/// Top-level boxes are white, everything below them gray.
+pub(crate) fn node_fill(depth_level: usize) -> Color32 {
+    if depth_level > 0 {
+        Color32::LIGHT_GRAY
+    } else {
+        Color32::WHITE
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{pert::ThreePointEstimate, EstimateApp, RollupMode, Task};
+
+pub const DEFAULT_ITERATIONS: usize = 10_000;
+pub const HISTOGRAM_BINS: usize = 20;
+
+/// One bucket of the simulated total distribution, covering `[start, end)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramBin {
+    pub start: f32,
+    pub end: f32,
+    pub count: usize,
+}
+
+/// Distribution of the project total after a Monte Carlo run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    pub iterations: usize,
+    pub mean: f32,
+    pub min: f32,
+    pub max: f32,
+    pub p50: f32,
+    pub p80: f32,
+    pub p95: f32,
+    pub histogram: Vec<HistogramBin>,
+}
+
+impl SimulationResult {
+    fn from_samples(mut samples: Vec<f32>) -> Self {
+        samples.sort_by(f32::total_cmp);
+        let iterations = samples.len();
+        let min = samples.first().copied().unwrap_or(0.0);
+        let max = samples.last().copied().unwrap_or(0.0);
+        let mean = if iterations > 0 {
+            samples.iter().sum::<f32>() / iterations as f32
+        } else {
+            0.0
+        };
+        Self {
+            iterations,
+            mean,
+            min,
+            max,
+            p50: percentile(&samples, 0.50),
+            p80: percentile(&samples, 0.80),
+            p95: percentile(&samples, 0.95),
+            histogram: histogram(&samples, min, max, HISTOGRAM_BINS),
+        }
+    }
+}
+
+/// Everything in a document that `simulate` depends on: the shape of the tree, every
+/// estimate and every rollup mode, but not names or display settings. A simulation stays
+/// valid for as long as the inputs of the document are equal to the ones it was run on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationInputs {
+    /// Depth, range and rollup mode of every task in pre-order.
+    tasks: Vec<(usize, ThreePointEstimate, RollupMode)>,
+}
+
+impl SimulationInputs {
+    pub fn of(app: &EstimateApp) -> Self {
+        let mut inputs = Self { tasks: Vec::new() };
+        for task in app.get_tasks() {
+            inputs.push(app, task, 0);
+        }
+        inputs
+    }
+
+    fn push(&mut self, app: &EstimateApp, task: &Task, depth: usize) {
+        self.tasks.push((
+            depth,
+            task.three_point_estimate(),
+            app.rollup_mode_for(task),
+        ));
+        for child in &task.children {
+            self.push(app, child, depth + 1);
+        }
+    }
+}
+
+/// Samples every task in `app` `iterations` times and returns the distribution of the total.
+/// Each sample is rolled up the same way `EstimateApp::rollups` combines the single estimates.
+/// Tasks without a range always contribute their single `estimate`.
+/// The same `seed` always gives the same result.
+pub fn simulate(app: &EstimateApp, iterations: usize, seed: u64) -> SimulationResult {
+    let mut rng = Rng::new(seed);
+    let samples = (0..iterations)
+        .map(|_| {
+            app.get_tasks()
+                .iter()
+                .map(|task| sample_task(app, task, &mut rng))
+                .sum()
+        })
+        .collect();
+
+    SimulationResult::from_samples(samples)
+}
+
+/// Samples `task` and its children, combined according to the task's rollup mode.
+fn sample_task(app: &EstimateApp, task: &Task, rng: &mut Rng) -> f32 {
+    let own = sample_triangular(&task.three_point_estimate(), rng);
+    let children: f32 = task
+        .children
+        .iter()
+        .map(|child| sample_task(app, child, rng))
+        .sum();
+    app.rollup_mode_for(task)
+        .combine(own, children, !task.children.is_empty())
+}
+
+/// Draws from a triangular distribution over `[optimistic, pessimistic]` peaking at `most_likely`.
+fn sample_triangular(range: &ThreePointEstimate, rng: &mut Rng) -> f32 {
+    let (low, mode, high) = (range.optimistic, range.most_likely, range.pessimistic);
+    if high <= low {
+        return mode;
+    }
+    let u = rng.next_f32();
+    let split = (mode - low) / (high - low);
+    if u < split {
+        low + (u * (high - low) * (mode - low)).sqrt()
+    } else {
+        high - ((1.0 - u) * (high - low) * (high - mode)).sqrt()
+    }
+}
+
+/// Nearest-rank percentile of already sorted `samples`.
+fn percentile(samples: &[f32], fraction: f32) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let rank = (fraction * samples.len() as f32).ceil() as usize;
+    samples[rank.clamp(1, samples.len()) - 1]
+}
+
+fn histogram(samples: &[f32], min: f32, max: f32, bins: usize) -> Vec<HistogramBin> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+    let width = ((max - min) / bins as f32).max(f32::EPSILON);
+    let mut histogram: Vec<HistogramBin> = (0..bins)
+        .map(|i| HistogramBin {
+            start: min + i as f32 * width,
+            end: min + (i + 1) as f32 * width,
+            count: 0,
+        })
+        .collect();
+    for sample in samples {
+        let index = (((sample - min) / width) as usize).min(bins - 1);
+        histogram[index].count += 1;
+    }
+    histogram
+}
+
+/// Small xorshift64* generator, so the simulation is reproducible and works the same on web.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift must never be seeded with zero.
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform value in `[0, 1)`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with_ranges() -> EstimateApp {
+        let mut app = EstimateApp::new();
+        let phase = app.add_task("Phase");
+        let design = app.add_child_task(&phase, "Design", 0.0).unwrap();
+        app.find_mut_task(&design)
+            .unwrap()
+            .set_three_point(ThreePointEstimate::new(2.0, 4.0, 12.0));
+        app.add_child_task(&phase, "Review", 3.0).unwrap();
+        app
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_result() {
+        let app = app_with_ranges();
+        assert_eq!(simulate(&app, 1_000, 42), simulate(&app, 1_000, 42));
+        assert_ne!(simulate(&app, 1_000, 42), simulate(&app, 1_000, 43));
+    }
+
+    #[test]
+    fn percentiles_stay_within_the_ranges() {
+        let app = app_with_ranges();
+        let result = simulate(&app, DEFAULT_ITERATIONS, 7);
+        assert_eq!(result.iterations, DEFAULT_ITERATIONS);
+        assert!(result.min >= 5.0 && result.max <= 15.0, "{result:?}");
+        assert!(result.min <= result.p50);
+        assert!(result.p50 <= result.p80);
+        assert!(result.p80 <= result.p95);
+        assert!(result.p95 <= result.max);
+        // The triangular distribution has the PERT mean (2 + 4 + 12) / 3 + 3.
+        assert!((result.mean - 9.0).abs() < 0.1, "{result:?}");
+        let counted: usize = result.histogram.iter().map(|bin| bin.count).sum();
+        assert_eq!(result.histogram.len(), HISTOGRAM_BINS);
+        assert_eq!(counted, DEFAULT_ITERATIONS);
+    }
+
+    #[test]
+    fn single_estimates_do_not_vary() {
+        let mut app = EstimateApp::new();
+        let task = app.add_task("Fixed");
+        app.find_mut_task(&task).unwrap().estimate = 6.0;
+        let result = simulate(&app, 100, 1);
+        assert_eq!(
+            (result.min, result.p50, result.p95, result.max),
+            (6.0, 6.0, 6.0, 6.0)
+        );
+    }
+
+    #[test]
+    fn inputs_only_change_with_what_the_simulation_reads() {
+        let mut app = app_with_ranges();
+        let inputs = SimulationInputs::of(&app);
+        let phase = app.get_tasks()[0].id.clone();
+
+        app.find_mut_task(&phase).unwrap().name = "Renamed".into();
+        app.max_node_width = 400.0;
+        assert_eq!(SimulationInputs::of(&app), inputs);
+
+        app.find_mut_task(&phase).unwrap().rollup_mode = Some(RollupMode::Max);
+        assert_ne!(SimulationInputs::of(&app), inputs);
+        app.find_mut_task(&phase).unwrap().rollup_mode = None;
+        app.add_child_task(&phase, "Extra", 0.0);
+        assert_ne!(SimulationInputs::of(&app), inputs);
+    }
+
+    #[test]
+    fn takes_nearest_rank_percentiles() {
+        let samples: Vec<f32> = (1..=10).map(|value| value as f32).collect();
+        assert_eq!(percentile(&samples, 0.5), 5.0);
+        assert_eq!(percentile(&samples, 0.8), 8.0);
+        assert_eq!(percentile(&samples, 0.95), 10.0);
+        assert_eq!(percentile(&[], 0.5), 0.0);
+    }
+}
@@ -0,0 +1,79 @@
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+
+use crate::{EstimateUnits, SimulationResult};
+
+const HISTOGRAM_HEIGHT: f32 = 120.0;
+
+/// Shows the percentiles and histogram of a Monte Carlo run.
+pub fn draw_simulation(ui: &mut egui::Ui, result: &SimulationResult, units: &EstimateUnits) {
+    egui::Grid::new("simulation_grid")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for (label, value) in [
+                ("P50", result.p50),
+                ("P80", result.p80),
+                ("P95", result.p95),
+                ("Mean", result.mean),
+                ("Min", result.min),
+                ("Max", result.max),
+            ] {
+                ui.label(label);
+                ui.label(units.format(value));
+                ui.end_row();
+            }
+        });
+    ui.add_space(8.0);
+    draw_histogram(ui, result);
+    ui.label(format!("{} iterations", result.iterations));
+}
+
+fn draw_histogram(ui: &mut egui::Ui, result: &SimulationResult) {
+    let (response, painter) = ui.allocate_painter(
+        Vec2::new(ui.available_width(), HISTOGRAM_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let rect = response.rect;
+    painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::DARK_GRAY));
+
+    let max_count = result
+        .histogram
+        .iter()
+        .map(|bin| bin.count)
+        .max()
+        .unwrap_or(0);
+    if max_count == 0 {
+        return;
+    }
+
+    let bin_width = rect.width() / result.histogram.len() as f32;
+    for (index, bin) in result.histogram.iter().enumerate() {
+        let height = rect.height() * bin.count as f32 / max_count as f32;
+        let bar = Rect::from_min_max(
+            Pos2::new(
+                rect.left() + index as f32 * bin_width,
+                rect.bottom() - height,
+            ),
+            Pos2::new(rect.left() + (index + 1) as f32 * bin_width, rect.bottom()),
+        );
+        painter.rect(
+            bar.shrink2(Vec2::new(1.0, 0.0)),
+            0.0,
+            Color32::LIGHT_BLUE,
+            Stroke::NONE,
+        );
+    }
+
+    // Mark the percentiles on the histogram.
+    let range = (result.max - result.min).max(f32::EPSILON);
+    for (value, color) in [
+        (result.p50, Color32::DARK_GREEN),
+        (result.p80, Color32::GOLD),
+        (result.p95, Color32::RED),
+    ] {
+        let x = rect.left() + rect.width() * (value - result.min) / range;
+        painter.line_segment(
+            [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+            Stroke::new(1.5, color),
+        );
+    }
+}
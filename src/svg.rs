@@ -0,0 +1,134 @@
+//! Exports the mindmap as a standalone SVG image with the same layout as on screen.
+
+use ecolor::Color32;
+use emath::{Align, Align2};
+
+use crate::scene::{mindmap_scene, Shape, Stroke, FONT_SIZE};
+use crate::{xml::escape, EstimateApp};
+
+/// Distance from the alphabetic baseline to the top and bottom of a line of text,
+/// relative to the font size.
+const ASCENT: f32 = 0.9;
+const DESCENT: f32 = 0.3;
+
+pub fn to_svg(app: &EstimateApp) -> String {
+    let scene = mindmap_scene(app);
+    let bounds = scene.bounds;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"{x} {y} {w} {h}\" font-family=\"Ubuntu, sans-serif\" font-size=\"{font}\">\n",
+        x = bounds.min.x,
+        y = bounds.min.y,
+        w = bounds.width(),
+        h = bounds.height(),
+        font = FONT_SIZE,
+    );
+    svg.push_str(&format!(
+        "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>\n",
+        bounds.min.x,
+        bounds.min.y,
+        bounds.width(),
+        bounds.height()
+    ));
+    for shape in &scene.shapes {
+        svg.push_str("  ");
+        svg.push_str(&shape_element(shape));
+        svg.push('\n');
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn shape_element(shape: &Shape) -> String {
+    match shape {
+        Shape::Rect {
+            rect,
+            rounding,
+            fill,
+            stroke,
+        } => format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" {} {}/>",
+            rect.min.x,
+            rect.min.y,
+            rect.width(),
+            rect.height(),
+            rounding,
+            fill_attribute(*fill),
+            stroke_attributes(*stroke)
+        ),
+        Shape::Line { points, stroke } => format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {}/>",
+            points[0].x,
+            points[0].y,
+            points[1].x,
+            points[1].y,
+            stroke_attributes(*stroke)
+        ),
+        Shape::Circle {
+            center,
+            radius,
+            fill,
+            stroke,
+        } => format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {} {}/>",
+            center.x,
+            center.y,
+            radius,
+            fill_attribute(*fill),
+            stroke_attributes(*stroke)
+        ),
+        Shape::Text {
+            position,
+            anchor,
+            text,
+            color,
+        } => {
+            let text_anchor = match anchor.x() {
+                Align::Min => "start",
+                Align::Center => "middle",
+                Align::Max => "end",
+            };
+            format!(
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"{}\" {}>{}</text>",
+                position.x,
+                position.y + baseline_offset(*anchor),
+                text_anchor,
+                fill_attribute(*color),
+                escape(text)
+            )
+        }
+    }
+}
+
+/// Moves the anchor point of a text to its baseline, which is where SVG places text.
+fn baseline_offset(anchor: Align2) -> f32 {
+    match anchor.y() {
+        Align::Min => ASCENT * FONT_SIZE,
+        Align::Center => (ASCENT - DESCENT) / 2.0 * FONT_SIZE,
+        Align::Max => -DESCENT * FONT_SIZE,
+    }
+}
+
+fn fill_attribute(color: Color32) -> String {
+    if color.a() == 0 {
+        "fill=\"none\"".to_owned()
+    } else {
+        format!("fill=\"{}\"", color_value(color))
+    }
+}
+
+fn stroke_attributes(stroke: Stroke) -> String {
+    if stroke.is_empty() {
+        "stroke=\"none\"".to_owned()
+    } else {
+        format!(
+            "stroke=\"{}\" stroke-width=\"{}\"",
+            color_value(stroke.color),
+            stroke.width
+        )
+    }
+}
+
+fn color_value(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
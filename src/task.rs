@@ -1,35 +1,54 @@
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+use crate::{pert::ThreePointEstimate, rollup::RollupMode};
+
+#[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)] // fields missing from a saved task get their default value, an empty `id` is repaired on load
 pub struct Task {
     pub id: String,
     pub name: String,
-    pub estimate: i32,
+    /// Hours, or story points if the document's unit is story points. See `EstimateUnits`.
+    pub estimate: f32,
+    /// Optional optimistic / most likely / pessimistic range.
+    /// Documents saved before this existed load as `None`, which is treated as a degenerate range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub three_point: Option<ThreePointEstimate>,
+    /// Overrides `EstimateApp::rollup_mode` for this task.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollup_mode: Option<RollupMode>,
     pub children: Vec<Task>,
 }
 
 impl Task {
-    pub fn new(id: &str, name: &str, estimate: i32) -> Self {
+    pub fn new(id: &str, name: &str, estimate: f32) -> Self {
         Self {
             id: id.to_string(),
             name: name.to_string(),
             estimate,
+            three_point: None,
+            rollup_mode: None,
             children: Vec::new(),
         }
     }
-    fn get_child_id(&self) -> String {
-        //let id = format!("{}-{}", &self.id, self.children.len());
-        let mut counter = self.children.len();
-        loop {
-            let candidate = format!("{}-{}", self.id, counter);
-            if self.children.iter().all(|child| child.id != candidate) {
-                return candidate;
-            }
-            counter += 1;
-        }
-    }
-    pub fn add_child_task(&mut self, name: &str, estimate: i32) -> String {
-        let child = Task::new(&self.get_child_id(), name, estimate);
-        let id = child.id.clone();
+    /// Adds a child with the given `id`, which should come from `EstimateApp::generate_task_id`.
+    pub fn add_child_task(&mut self, id: String, name: &str, estimate: f32) -> String {
+        let child = Task::new(&id, name, estimate);
         self.children.push(child);
         id
     }
+
+    /// Returns the three-point range of this task, falling back to a degenerate range around `estimate`.
+    pub fn three_point_estimate(&self) -> ThreePointEstimate {
+        self.three_point
+            .unwrap_or_else(|| ThreePointEstimate::degenerate(self.estimate))
+    }
+
+    /// Sets the three-point range and keeps `estimate` in sync with the most likely value.
+    /// A degenerate range is stored as a plain single-value estimate.
+    pub fn set_three_point(&mut self, estimate: ThreePointEstimate) {
+        self.estimate = estimate.most_likely;
+        self.three_point = if estimate.is_degenerate() {
+            None
+        } else {
+            Some(estimate)
+        };
+    }
 }
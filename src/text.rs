@@ -0,0 +1,163 @@
+//! Measures and wraps node labels with the font egui draws them in,
+//! so boxes can be sized to their text on screen and in the image exports alike.
+
+use std::sync::OnceLock;
+
+use ab_glyph::{Font as _, FontRef, PxScale, PxScaleFont, ScaleFont as _};
+
+const ELLIPSIS: char = '…';
+
+/// The font egui uses for proportional text.
+pub(crate) fn font() -> &'static FontRef<'static> {
+    static FONT: OnceLock<FontRef<'static>> = OnceLock::new();
+    FONT.get_or_init(|| {
+        FontRef::try_from_slice(epaint_default_fonts::UBUNTU_LIGHT)
+            .expect("the embedded egui font is valid")
+    })
+}
+
+/// `font()` at an em size of `size`, as in the SVG export.
+pub(crate) fn scaled_font(size: f32) -> PxScaleFont<&'static FontRef<'static>> {
+    let font = font();
+    let units_per_em = font.units_per_em().unwrap_or(1000.0);
+    font.as_scaled(PxScale::from(size * font.height_unscaled() / units_per_em))
+}
+
+/// Height of one line of text at `size`.
+pub(crate) fn line_height(size: f32) -> f32 {
+    scaled_font(size).height()
+}
+
+/// Width of a single line of text at `size`.
+pub(crate) fn text_width(text: &str, size: f32) -> f32 {
+    let scaled = scaled_font(size);
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            width += scaled.kern(previous, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Breaks `text` into lines no wider than `max_width`, between words where possible.
+/// Text that doesn't fit on `max_lines` lines is cut off with an ellipsis.
+pub(crate) fn wrap(text: &str, max_width: f32, max_lines: usize, size: f32) -> Vec<String> {
+    let fits = |line: &str| text_width(line, size) <= max_width;
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() {
+            word.to_owned()
+        } else {
+            format!("{current} {word}")
+        };
+        if fits(&candidate) {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        current = word.to_owned();
+        // A word wider than a whole line is broken wherever it has to be.
+        while !fits(&current) && current.chars().count() > 1 {
+            let split = split_point(&current, fits);
+            lines.push(current[..split].to_owned());
+            current = current[split..].to_owned();
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            *last = ellipsize(&format!("{last}{ELLIPSIS}"), max_width, size);
+        }
+    }
+    lines
+}
+
+/// `text` on a single line, cut off with an ellipsis if it is wider than `max_width`.
+pub(crate) fn ellipsize(text: &str, max_width: f32, size: f32) -> String {
+    if text_width(text, size) <= max_width {
+        return text.to_owned();
+    }
+    let text = text.trim_end_matches(ELLIPSIS);
+    let split = split_point(text, |head| {
+        text_width(&format!("{}{ELLIPSIS}", head.trim_end()), size) <= max_width
+    });
+    format!("{}{ELLIPSIS}", text[..split].trim_end())
+}
+
+/// Byte index of the longest non-empty prefix of `text` that `fits`, at least one character.
+fn split_point(text: &str, fits: impl Fn(&str) -> bool) -> usize {
+    let mut boundaries = text.char_indices().map(|(index, _)| index).skip(1);
+    let first = boundaries.next().unwrap_or(text.len());
+    boundaries
+        .take_while(|&index| fits(&text[..index]))
+        .last()
+        .unwrap_or(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f32 = 14.0;
+
+    #[test]
+    fn measures_longer_text_as_wider() {
+        assert_eq!(text_width("", SIZE), 0.0);
+        assert!(text_width("Design", SIZE) < text_width("Design review", SIZE));
+        assert!(text_width("Design", SIZE) < text_width("Design", 2.0 * SIZE));
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        let width = text_width("Design review", SIZE);
+        assert_eq!(
+            wrap("Design review  of the", width, 5, SIZE),
+            ["Design review", "of the"]
+        );
+        assert_eq!(wrap("Design review", width, 5, SIZE), ["Design review"]);
+        assert_eq!(wrap("", width, 5, SIZE), [""]);
+    }
+
+    #[test]
+    fn breaks_words_wider_than_a_line() {
+        let width = text_width("abcd", SIZE);
+        let lines = wrap("abcdefghij", width, 5, SIZE);
+        assert_eq!(lines.concat(), "abcdefghij");
+        assert!(lines.len() >= 3, "{lines:?}");
+        assert!(lines.iter().all(|line| text_width(line, SIZE) <= width));
+        // A single character is kept even if it doesn't fit.
+        assert_eq!(wrap("W", 1.0, 5, SIZE), ["W"]);
+    }
+
+    #[test]
+    fn cuts_off_text_beyond_the_last_line_with_an_ellipsis() {
+        let width = text_width("Design review", SIZE);
+        let lines = wrap("Design review of the backend", width, 1, SIZE);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with(ELLIPSIS), "{lines:?}");
+        assert!(lines[0].starts_with("Design"), "{lines:?}");
+        assert!(text_width(&lines[0], SIZE) <= width);
+    }
+
+    #[test]
+    fn ellipsizes_only_text_that_does_not_fit() {
+        let width = text_width("Design", SIZE);
+        assert_eq!(ellipsize("Design", width, SIZE), "Design");
+        let cut = ellipsize("Design review", width, SIZE);
+        assert!(cut.ends_with(ELLIPSIS), "{cut}");
+        assert!(!cut.contains(' '), "{cut}");
+        assert!(text_width(&cut, SIZE) <= width);
+    }
+}
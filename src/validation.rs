@@ -0,0 +1,60 @@
+use crate::EstimateApp;
+
+/// A problem with one task found by `validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    pub task_id: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.task_id, self.message)
+    }
+}
+
+/// Checks a document for estimates that make the totals meaningless or misleading:
+/// invalid numbers, unordered three-point ranges, leaves without an estimate,
+/// unnamed tasks and parents whose estimate conflicts with their children.
+pub fn validate(app: &EstimateApp) -> Vec<ValidationIssue> {
+    let rollups = app.rollups();
+    let mut issues = Vec::new();
+    for task in app.flatten_tasks() {
+        let mut issue = |message: String| {
+            issues.push(ValidationIssue {
+                task_id: task.id.clone(),
+                message,
+            })
+        };
+        let name = format!("\"{}\"", task.name);
+        if task.name.trim().is_empty() {
+            issue("the task has no name".to_owned());
+        }
+        if !task.estimate.is_finite() || task.estimate < 0.0 {
+            issue(format!(
+                "{} has an invalid estimate {}",
+                name, task.estimate
+            ));
+        }
+        if let Some(range) = task.three_point {
+            if !(range.optimistic <= range.most_likely && range.most_likely <= range.pessimistic) {
+                issue(format!(
+                    "{} has a range that is not optimistic <= most likely <= pessimistic",
+                    name
+                ));
+            }
+        }
+        if task.children.is_empty() && task.estimate == 0.0 {
+            issue(format!("{} has no estimate", name));
+        }
+        if let Some(rollup) = rollups.get(&task.id).filter(|rollup| rollup.conflict) {
+            issue(format!(
+                "{} is estimated at {} but its children add up to {}",
+                name,
+                app.units.format(task.estimate),
+                app.units.format(rollup.children)
+            ));
+        }
+    }
+    issues
+}
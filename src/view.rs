@@ -0,0 +1,62 @@
+//! Panning and zooming of the canvas. The layout works in mindmap coordinates, with the root
+//! box centered on the origin, and every box is mapped to the screen through a `View`
+//! before it is painted or hit-tested.
+
+use emath::{Pos2, Rect, TSTransform, Vec2};
+
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 4.0;
+
+/// Empty space left around the mindmap by "Fit all", in screen points.
+const FIT_MARGIN: f32 = 40.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct View {
+    /// Where the origin of the mindmap is, relative to the center of the canvas.
+    pub offset: Vec2,
+    pub zoom: f32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl View {
+    /// Maps mindmap coordinates to screen coordinates on `canvas`.
+    pub fn to_screen(&self, canvas: Rect) -> TSTransform {
+        TSTransform::new(canvas.center().to_vec2() + self.offset, self.zoom)
+    }
+
+    pub fn pan(&mut self, delta: Vec2) {
+        self.offset += delta;
+    }
+
+    /// Zooms by `factor`, keeping the point `anchor` (relative to the canvas center) in place.
+    pub fn zoom_by(&mut self, factor: f32, anchor: Vec2) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let factor = zoom / self.zoom;
+        self.offset = anchor + (self.offset - anchor) * factor;
+        self.zoom = zoom;
+    }
+
+    /// Zooms and pans so that `bounds`, in mindmap coordinates, fills a canvas of `canvas_size`.
+    /// Never zooms in beyond 100%, so a small mindmap isn't blown up.
+    pub fn fit(&mut self, bounds: Rect, canvas_size: Vec2) {
+        let available = (canvas_size - Vec2::splat(FIT_MARGIN * 2.0)).max(Vec2::splat(1.0));
+        let zoom = (available.x / bounds.width())
+            .min(available.y / bounds.height())
+            .min(1.0);
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center_on(bounds.center());
+    }
+
+    /// Pans so that `position`, in mindmap coordinates, is in the middle of the canvas.
+    pub fn center_on(&mut self, position: Pos2) {
+        self.offset = -position.to_vec2() * self.zoom;
+    }
+}
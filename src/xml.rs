@@ -0,0 +1,18 @@
+//! Helpers for the XML and HTML based export formats.
+
+/// Escapes text for use inside a double-quoted XML attribute or element.
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\t' => escaped.push_str("&#9;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}